@@ -0,0 +1,369 @@
+// A 2D cellular automaton: every step replaces each cell with the result of a rule applied to the
+// cell and its neighbourhood, all cells updating at once. Rules where one cell can set off its
+// neighbours within the same step (like day 11's flashing octopuses) use cascade instead.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Neighbourhood {
+    // The 8 surrounding cells
+    Moore,
+    // The 4 orthogonally adjacent cells
+    VonNeumann,
+    // The full 3x3 block including the cell itself, in reading order, for rules that turn the
+    // block into an index (like day 20's image enhancement)
+    Index3x3,
+}
+
+impl Neighbourhood {
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Neighbourhood::Moore => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+            Neighbourhood::VonNeumann => &[(-1, 0), (0, -1), (0, 1), (1, 0)],
+            Neighbourhood::Index3x3 => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 0),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary<T> {
+    // Cells outside the grid always read as this value
+    Fixed(T),
+    // The grid is a torus: leaving one edge re-enters from the opposite one
+    Wrapping,
+    // The grid sits on an infinite canvas of this value. Each step grows the grid by one cell on
+    // every side, and the background itself is updated by the rule.
+    Infinite(T),
+}
+
+pub trait Rule<T> {
+    fn next(&self, cell: T, neighbours: &[T]) -> T;
+}
+
+impl<T, F: Fn(T, &[T]) -> T> Rule<T> for F {
+    fn next(&self, cell: T, neighbours: &[T]) -> T {
+        self(cell, neighbours)
+    }
+}
+
+// A rule for cascading steps. Every cell is first advanced on its own, then each cell that fires
+// signals its neighbours, which can make them fire in turn. A cell fires at most once per step.
+pub trait CascadeRule<T> {
+    // The cell's value at the start of the step, before any neighbour has fired
+    fn start(&self, cell: T) -> T;
+    fn fires(&self, cell: T) -> bool;
+    // One of the cell's neighbours fired
+    fn signal(&self, cell: T) -> T;
+    // The value a cell that fired ends the step with
+    fn settle(&self, cell: T) -> T;
+}
+
+pub struct Automaton<T, R> {
+    cells: Vec<Vec<T>>,
+    neighbourhood: Neighbourhood,
+    boundary: Boundary<T>,
+    rule: R,
+}
+
+impl<T: Copy, R> Automaton<T, R> {
+    pub fn new(
+        cells: Vec<Vec<T>>,
+        neighbourhood: Neighbourhood,
+        boundary: Boundary<T>,
+        rule: R,
+    ) -> Self {
+        Automaton {
+            cells,
+            neighbourhood,
+            boundary,
+            rule,
+        }
+    }
+
+    pub fn cells(&self) -> &Vec<Vec<T>> {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> Vec<Vec<T>> {
+        self.cells
+    }
+
+    // The value of every cell outside the grid, if the boundary is infinite
+    pub fn background(&self) -> Option<T> {
+        match self.boundary {
+            Boundary::Infinite(background) => Some(background),
+            _ => None,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cells.first().map_or(0, |row| row.len())
+    }
+
+    pub fn get(&self, row: isize, col: isize) -> T {
+        let (rows, cols) = (self.rows() as isize, self.cols() as isize);
+        if row >= 0 && row < rows && col >= 0 && col < cols {
+            return self.cells[row as usize][col as usize];
+        }
+        match self.boundary {
+            Boundary::Fixed(value) | Boundary::Infinite(value) => value,
+            Boundary::Wrapping => {
+                self.cells[row.rem_euclid(rows) as usize][col.rem_euclid(cols) as usize]
+            }
+        }
+    }
+
+    // The cells in the grid next to the given one. Cells past the edge only exist on a wrapping
+    // grid.
+    fn neighbours_in_grid(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let (rows, cols) = (self.rows() as isize, self.cols() as isize);
+        self.neighbourhood
+            .offsets()
+            .iter()
+            .map(|(i, j)| (row as isize + i, col as isize + j))
+            .filter(|&(row, col)| {
+                matches!(self.boundary, Boundary::Wrapping)
+                    || (row >= 0 && row < rows && col >= 0 && col < cols)
+            })
+            .map(|(row, col)| (row.rem_euclid(rows) as usize, col.rem_euclid(cols) as usize))
+            .collect()
+    }
+}
+
+impl<T: Copy, R: Rule<T>> Automaton<T, R> {
+    fn next_cell(&self, row: isize, col: isize) -> T {
+        let neighbours: Vec<T> = self
+            .neighbourhood
+            .offsets()
+            .iter()
+            .map(|(i, j)| self.get(row + i, col + j))
+            .collect();
+        self.rule.next(self.get(row, col), &neighbours)
+    }
+
+    pub fn step(&mut self) {
+        // An infinite grid grows by one on each side, since the cells just outside it can change
+        let margin = match self.boundary {
+            Boundary::Infinite(_) => 1,
+            _ => 0,
+        };
+        let cells = (-margin..self.rows() as isize + margin)
+            .map(|row| {
+                (-margin..self.cols() as isize + margin)
+                    .map(|col| self.next_cell(row, col))
+                    .collect()
+            })
+            .collect();
+        if let Boundary::Infinite(background) = self.boundary {
+            let neighbours = vec![background; self.neighbourhood.offsets().len()];
+            self.boundary = Boundary::Infinite(self.rule.next(background, &neighbours));
+        }
+        self.cells = cells;
+    }
+}
+
+impl<T: Copy, R: CascadeRule<T>> Automaton<T, R> {
+    // Runs one cascading step and returns how many cells fired. Only cells in the grid take part,
+    // so the background of an infinite grid never changes.
+    pub fn cascade(&mut self) -> usize {
+        for cell in self.cells.iter_mut().flatten() {
+            *cell = self.rule.start(*cell);
+        }
+        let mut fired = vec![vec![false; self.cols()]; self.rows()];
+        let mut pending = Vec::new();
+        for (row, cells) in self.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if self.rule.fires(*cell) {
+                    fired[row][col] = true;
+                    pending.push((row, col));
+                }
+            }
+        }
+        let mut count = pending.len();
+        while let Some((row, col)) = pending.pop() {
+            for (row, col) in self.neighbours_in_grid(row, col) {
+                let cell = self.rule.signal(self.cells[row][col]);
+                self.cells[row][col] = cell;
+                if !fired[row][col] && self.rule.fires(cell) {
+                    fired[row][col] = true;
+                    pending.push((row, col));
+                    count += 1;
+                }
+            }
+        }
+        for (cells, fired) in self.cells.iter_mut().zip(fired) {
+            for (cell, _) in cells.iter_mut().zip(fired).filter(|(_, fired)| *fired) {
+                *cell = self.rule.settle(*cell);
+            }
+        }
+        count
+    }
+}
+
+// Conway's Game of Life: a live cell survives with 2 or 3 live neighbours, and a dead cell comes
+// alive with exactly 3. Meant for the Moore neighbourhood.
+pub fn life(cell: bool, neighbours: &[bool]) -> bool {
+    let alive = neighbours.iter().filter(|n| **n).count();
+    alive == 3 || (cell && alive == 2)
+}
+
+#[cfg(test)]
+fn to_cells(rows: &[&str]) -> Vec<Vec<bool>> {
+    rows.iter()
+        .map(|row| row.chars().map(|c| c == '#').collect())
+        .collect()
+}
+
+#[test]
+fn test_life_blinker() {
+    let mut automaton = Automaton::new(
+        to_cells(&[".....", "..#..", "..#..", "..#..", "....."]),
+        Neighbourhood::Moore,
+        Boundary::Fixed(false),
+        life,
+    );
+    automaton.step();
+    assert_eq!(
+        &to_cells(&[".....", ".....", ".###.", ".....", "....."]),
+        automaton.cells()
+    );
+    automaton.step();
+    assert_eq!(
+        &to_cells(&[".....", "..#..", "..#..", "..#..", "....."]),
+        automaton.cells()
+    );
+}
+
+#[test]
+fn test_life_glider_wraps() {
+    let glider = to_cells(&[".#...", "..#..", "###..", ".....", "....."]);
+    let mut automaton = Automaton::new(
+        glider.clone(),
+        Neighbourhood::Moore,
+        Boundary::Wrapping,
+        life,
+    );
+    // Every 4 steps the glider moves one cell down and right, so after 20 it's back where it
+    // started on a 5x5 torus
+    for _ in 0..20 {
+        automaton.step();
+    }
+    assert_eq!(&glider, automaton.cells());
+}
+
+#[test]
+fn test_von_neumann() {
+    // Count of live orthogonal neighbours
+    let mut automaton = Automaton::new(
+        vec![vec![0, 0, 0], vec![0, 1, 0], vec![0, 0, 0]],
+        Neighbourhood::VonNeumann,
+        Boundary::Fixed(0),
+        |_, neighbours: &[u8]| neighbours.iter().sum(),
+    );
+    automaton.step();
+    assert_eq!(
+        &vec![vec![0, 1, 0], vec![1, 0, 1], vec![0, 1, 0]],
+        automaton.cells()
+    );
+}
+
+#[test]
+fn test_infinite_background() {
+    // Every cell becomes the inverse of its own value, background included
+    let mut automaton = Automaton::new(
+        vec![vec![true]],
+        Neighbourhood::Index3x3,
+        Boundary::Infinite(false),
+        |cell: bool, neighbours: &[bool]| {
+            assert_eq!(cell, neighbours[4]);
+            !cell
+        },
+    );
+    automaton.step();
+    assert_eq!(Some(true), automaton.background());
+    assert_eq!(
+        &vec![
+            vec![true, true, true],
+            vec![true, false, true],
+            vec![true, true, true]
+        ],
+        automaton.cells()
+    );
+    assert!(automaton.get(-10, 10));
+}
+
+#[test]
+fn test_cascade() {
+    // Day 11's smaller example: every cell goes up by one, anything over 9 flashes, raising its
+    // neighbours, and ends the step at 0
+    struct Flash;
+    impl CascadeRule<u8> for Flash {
+        fn start(&self, cell: u8) -> u8 {
+            cell + 1
+        }
+        fn fires(&self, cell: u8) -> bool {
+            cell > 9
+        }
+        fn signal(&self, cell: u8) -> u8 {
+            cell + 1
+        }
+        fn settle(&self, _: u8) -> u8 {
+            0
+        }
+    }
+    let to_digits = |rows: &[&str]| -> Vec<Vec<u8>> {
+        rows.iter()
+            .map(|row| row.bytes().map(|b| b - b'0').collect())
+            .collect()
+    };
+    let mut automaton = Automaton::new(
+        to_digits(&["11111", "19991", "19191", "19991", "11111"]),
+        Neighbourhood::Moore,
+        Boundary::Fixed(0),
+        Flash,
+    );
+    assert_eq!(9, automaton.cascade());
+    assert_eq!(
+        &to_digits(&["34543", "40004", "50005", "40004", "34543"]),
+        automaton.cells()
+    );
+    assert_eq!(0, automaton.cascade());
+    assert_eq!(
+        &to_digits(&["45654", "51115", "61116", "51115", "45654"]),
+        automaton.cells()
+    );
+
+    // On a torus a corner's neighbours include the far edges
+    let mut automaton = Automaton::new(
+        to_digits(&["9000", "0000", "0000", "0000"]),
+        Neighbourhood::Moore,
+        Boundary::Wrapping,
+        Flash,
+    );
+    assert_eq!(1, automaton.cascade());
+    assert_eq!(
+        &to_digits(&["0212", "2212", "1111", "2212"]),
+        automaton.cells()
+    );
+}
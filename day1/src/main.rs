@@ -1,27 +1,216 @@
+use std::collections::VecDeque;
 use std::env;
 use std::fs;
 use std::io;
 use std::io::BufRead;
+use std::str::FromStr;
 
+// One window of consecutive depths
+#[derive(Debug, PartialEq)]
+struct Window {
+    sum: u64,
+    min: u32,
+    max: u32,
+    size: usize,
+}
+
+impl Window {
+    fn mean(&self) -> f64 {
+        self.sum as f64 / self.size as f64
+    }
+}
+
+// How the sum changes from each window to the next
+#[derive(Debug, Default, PartialEq)]
+struct Changes {
+    increases: u32,
+    decreases: u32,
+    plateaus: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Stat {
+    Sum,
+    Mean,
+    Min,
+    Max,
+    Increases,
+    Decreases,
+    Plateaus,
+}
+
+impl FromStr for Stat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(Stat::Sum),
+            "mean" => Ok(Stat::Mean),
+            "min" => Ok(Stat::Min),
+            "max" => Ok(Stat::Max),
+            "increases" => Ok(Stat::Increases),
+            "decreases" => Ok(Stat::Decreases),
+            "plateaus" => Ok(Stat::Plateaus),
+            _ => Err(format!(
+                "unknown stat {}, expected sum, mean, min, max, increases, decreases or plateaus",
+                s
+            )),
+        }
+    }
+}
+
+// Every full window of the given size, in order. There are none if the window is empty or longer
+// than the series.
+fn windows(window_size: usize, values: &[u32]) -> Vec<Window> {
+    if window_size == 0 {
+        return Vec::new();
+    }
+    values
+        .windows(window_size)
+        .map(|window| Window {
+            sum: window.iter().map(|v| *v as u64).sum(),
+            min: *window.iter().min().unwrap(),
+            max: *window.iter().max().unwrap(),
+            size: window_size,
+        })
+        .collect()
+}
+
+fn changes(window_size: usize, values: &[u32]) -> Changes {
+    let sums: Vec<u64> = windows(window_size, values).iter().map(|w| w.sum).collect();
+    let mut changes = Changes::default();
+    for pair in sums.windows(2) {
+        match pair[1].cmp(&pair[0]) {
+            std::cmp::Ordering::Greater => changes.increases += 1,
+            std::cmp::Ordering::Less => changes.decreases += 1,
+            std::cmp::Ordering::Equal => changes.plateaus += 1,
+        }
+    }
+    changes
+}
+
+// Per-window stats print one line per window, and change counts print a single total
+fn print_stat(stat: Stat, window_size: usize, values: &[u32]) {
+    let windows = windows(window_size, values);
+    let changes = changes(window_size, values);
+    match stat {
+        Stat::Sum => windows.iter().for_each(|w| println!("{}", w.sum)),
+        Stat::Mean => windows.iter().for_each(|w| println!("{}", w.mean())),
+        Stat::Min => windows.iter().for_each(|w| println!("{}", w.min)),
+        Stat::Max => windows.iter().for_each(|w| println!("{}", w.max)),
+        Stat::Increases => println!("{}", changes.increases),
+        Stat::Decreases => println!("{}", changes.decreases),
+        Stat::Plateaus => println!("{}", changes.plateaus),
+    }
+}
+
+// Counts increases for one window size while values arrive one at a time, keeping only the last
+// window_size + 1 of them
+struct StreamingIncreases {
+    window_size: usize,
+    recent: VecDeque<u32>,
+    count: u32,
+}
+
+impl StreamingIncreases {
+    fn new(window_size: usize) -> StreamingIncreases {
+        StreamingIncreases {
+            window_size,
+            recent: VecDeque::with_capacity(window_size + 1),
+            count: 0,
+        }
+    }
+
+    fn push(&mut self, value: u32) {
+        self.recent.push_back(value);
+        if self.recent.len() > self.window_size {
+            // Same comparison as count_windowed_increases: newest value against the one that
+            // just left the window
+            let oldest = self.recent.pop_front().unwrap();
+            if value > oldest {
+                self.count += 1;
+            }
+        }
+    }
+}
+
+fn count_windowed_increases_streaming<I: IntoIterator<Item = u32>>(
+    window_size: usize,
+    values: I,
+) -> u32 {
+    let mut counter = StreamingIncreases::new(window_size);
+    values.into_iter().for_each(|value| counter.push(value));
+    counter.count
+}
+
+fn read_values<R: BufRead>(reader: R) -> impl Iterator<Item = u32> {
+    reader
+        .lines()
+        .map(|line| line.unwrap().trim().parse().unwrap())
+}
+
+// Usage: day1 [file] [--window N --stat STAT] [--stream]
+// A file of - reads from stdin. --stream answers both parts (or --stat increases) in one pass
+// without holding the depths in memory.
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let filename = &args[1];
+    let mut filename = String::from("input.txt");
+    let mut window_size = 1;
+    let mut stat = None;
+    let mut stream = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--window" => window_size = args.next().expect("--window N").parse().unwrap(),
+            "--stat" => stat = Some(args.next().expect("--stat STAT").parse().unwrap()),
+            "--stream" => stream = true,
+            _ => filename = arg,
+        }
+    }
     println!("Reading file {}", filename);
-    let file = fs::File::open(filename).unwrap();
-    let reader = io::BufReader::new(file);
-    let mut all_values: Vec<u32> = Vec::new();
-    for (i, line) in reader.lines().enumerate() {
-        all_values.push(line.unwrap().parse().unwrap());
+    let reader: Box<dyn BufRead> = if filename == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(io::BufReader::new(fs::File::open(filename).unwrap()))
+    };
+    if stream {
+        match stat {
+            Some(Stat::Increases) => {
+                println!(
+                    "{}",
+                    count_windowed_increases_streaming(window_size, read_values(reader))
+                );
+                return;
+            }
+            Some(stat) => panic!("--stream only supports --stat increases, not {:?}", stat),
+            None => {}
+        }
+        let mut part1 = StreamingIncreases::new(1);
+        let mut part2 = StreamingIncreases::new(3);
+        for value in read_values(reader) {
+            part1.push(value);
+            part2.push(value);
+        }
+        println!("Part 1: {}", part1.count);
+        println!("Part 2: {}", part2.count);
+        return;
+    }
+    let all_values: Vec<u32> = read_values(reader).collect();
+    match stat {
+        Some(stat) => print_stat(stat, window_size, &all_values),
+        None => {
+            println!("Part 1: {}", count_windowed_increases(1, &all_values));
+            println!("Part 2: {}", count_windowed_increases(3, &all_values))
+        }
     }
-    println!("Part 1: {}", count_windowed_increases(1, &all_values));
-    println!("Part 2: {}", count_windowed_increases(3, &all_values))
 }
 
-fn count_windowed_increases(window_size: usize, values: &Vec<u32>) -> u32 {
+// Consecutive windows share all but their first and last values, so comparing those two is the
+// same as comparing the sums
+fn count_windowed_increases(window_size: usize, values: &[u32]) -> u32 {
     let mut count = 0;
-    for i in 0..values.len() - window_size {
+    for i in 0..values.len().saturating_sub(window_size) {
         if values[i + window_size] > values[i] {
-            count = count + 1;
+            count += 1;
         }
     }
     count
@@ -33,3 +222,53 @@ fn test() {
     assert_eq!(7, count_windowed_increases(1, &sample_values));
     assert_eq!(5, count_windowed_increases(3, &sample_values));
 }
+
+#[test]
+fn test_window_stats() {
+    let sample_values = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+    let sums: Vec<u64> = windows(3, &sample_values).iter().map(|w| w.sum).collect();
+    assert_eq!(vec![607, 618, 618, 617, 647, 716, 769, 792], sums);
+    assert_eq!(
+        Window {
+            sum: 618,
+            min: 200,
+            max: 210,
+            size: 3,
+        },
+        windows(3, &sample_values)[1]
+    );
+    assert_eq!(206.0, windows(3, &sample_values)[1].mean());
+    assert_eq!(
+        Changes {
+            increases: 5,
+            decreases: 1,
+            plateaus: 1,
+        },
+        changes(3, &sample_values)
+    );
+    for window_size in 1..4 {
+        assert_eq!(
+            count_windowed_increases(window_size, &sample_values),
+            changes(window_size, &sample_values).increases
+        );
+    }
+    assert!(windows(11, &sample_values).is_empty());
+    assert_eq!(0, count_windowed_increases(11, &sample_values));
+    assert_eq!(Changes::default(), changes(0, &sample_values));
+}
+
+#[test]
+fn test_streaming() {
+    let sample_values = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+    for window_size in 0..12 {
+        assert_eq!(
+            count_windowed_increases(window_size, &sample_values),
+            count_windowed_increases_streaming(window_size, sample_values.iter().copied())
+        );
+    }
+    let text = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+    assert_eq!(
+        5,
+        count_windowed_increases_streaming(3, read_values(text.as_bytes()))
+    );
+}
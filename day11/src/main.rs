@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use automaton::{Automaton, Boundary, CascadeRule, Neighbourhood};
 use std::env;
 use std::fs;
 use std::io;
@@ -34,99 +34,70 @@ fn read_file_to_vec(filename: String) -> Vec<String> {
     parsed_lines
 }
 
-fn part1(lines: &Vec<String>) -> u64 {
+fn part1(lines: &[String]) -> u64 {
     run_model(lines, 100)
 }
 
-fn run_model(lines: &Vec<String>, iters: usize) -> u64 {
-    let mut grid = to_grid(lines);
-    let mut total_flashers: u64 = 0;
-    for _ in 0..iters {
-        for x in 0..10 {
-            for y in 0..10 {
-                grid[x][y] += 1;
-            }
-        }
-        let mut flashers = HashSet::new();
-        mark_flashes(&mut grid, &mut flashers);
-        for (j, k) in &flashers {
-            grid[*j][*k] = 0;
-        }
-        total_flashers += flashers.len() as u64;
+// Each step every octopus gains one energy, and any over 9 flashes, giving one more to each of
+// its neighbours (which may make them flash too). Octopuses that flashed end the step at 0.
+struct Flash;
+
+impl CascadeRule<u64> for Flash {
+    fn start(&self, energy: u64) -> u64 {
+        energy + 1
     }
-    total_flashers
-}
 
-fn part2(lines: &Vec<String>) -> u64 {
-    let mut i = 0;
-    let mut grid = to_grid(lines);
-    loop {
-        i += 1;
-        for x in 0..10 {
-            for y in 0..10 {
-                grid[x][y] += 1;
-            }
-        }
-        let mut flashers = HashSet::new();
-        mark_flashes(&mut grid, &mut flashers);
-        for (j, k) in &flashers {
-            grid[*j][*k] = 0;
-        }
+    fn fires(&self, energy: u64) -> bool {
+        energy > 9
+    }
 
-        if flashers.len() == 100 {
-            return i;
-        }
+    fn signal(&self, energy: u64) -> u64 {
+        energy + 1
     }
-}
 
-fn to_grid(lines: &Vec<String>) -> [[u64; 10]; 10] {
-    let mut grid = [[0_u64; 10]; 10];
-    for (x, line) in lines.iter().enumerate() {
-        for (y, digit) in line.chars().enumerate() {
-            grid[x][y] = digit.to_digit(10).unwrap() as u64;
-        }
+    fn settle(&self, _: u64) -> u64 {
+        0
     }
-    grid
 }
 
-fn mark_flashes(grid: &mut [[u64; 10]; 10], flashers: &mut HashSet<(usize, usize)>) {
-    for x in 0..10 {
-        for y in 0..10 {
-            mark_if_flashing(x, y, grid, flashers);
-        }
-    }
+fn octopuses(lines: &[String]) -> Automaton<u64, Flash> {
+    Automaton::new(
+        to_grid(lines),
+        Neighbourhood::Moore,
+        Boundary::Fixed(0),
+        Flash,
+    )
+}
+
+fn run_model(lines: &[String], iters: usize) -> u64 {
+    let mut octopuses = octopuses(lines);
+    (0..iters).map(|_| octopuses.cascade() as u64).sum()
 }
 
-fn mark_if_flashing(
-    x: usize,
-    y: usize,
-    grid: &mut [[u64; 10]; 10],
-    flashers: &mut HashSet<(usize, usize)>,
-) {
-    let octopus_level = grid[x][y];
-    let coords = (x, y);
-    if octopus_level > 9 && !flashers.contains(&coords) {
-        flashers.insert(coords);
-        for (i, j) in [
-            (-1_i8, -1_i8),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ] {
-            let neighbor_x = x as i8 + i;
-            let neighbor_y = y as i8 + j;
-            if neighbor_x >= 0 && neighbor_x < 10 && neighbor_y >= 0 && neighbor_y < 10 {
-                grid[neighbor_x as usize][neighbor_y as usize] += 1;
-                mark_if_flashing(neighbor_x as usize, neighbor_y as usize, grid, flashers);
-            }
+fn part2(lines: &[String]) -> u64 {
+    let mut octopuses = octopuses(lines);
+    let all = octopuses.rows() * octopuses.cols();
+    let mut i = 0;
+    loop {
+        i += 1;
+        if octopuses.cascade() == all {
+            return i;
         }
     }
 }
 
+fn to_grid(lines: &[String]) -> Vec<Vec<u64>> {
+    lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.chars()
+                .map(|digit| digit.to_digit(10).unwrap() as u64)
+                .collect()
+        })
+        .collect()
+}
+
 #[test]
 fn test_part1() {
     let sample_data = read_file_to_vec(String::from("test.txt"));
@@ -0,0 +1,110 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::time::Instant;
+
+fn main() {
+    let now = Instant::now();
+    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let parsed_lines = read_file_to_vec(filename);
+    let part_1_answer = part1(&parsed_lines);
+    println!(
+        "Part 1 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_1_answer
+    );
+    let part_2_answer = part2(&parsed_lines);
+    println!(
+        "Part 2 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_2_answer
+    );
+}
+
+fn read_file_to_vec(filename: String) -> Vec<String> {
+    println!("Reading file {}", filename);
+    let file = fs::File::open(filename).unwrap();
+    let reader = io::BufReader::new(file);
+    let mut parsed_lines: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        parsed_lines.push(line.unwrap());
+    }
+    parsed_lines
+}
+
+fn part1(lines: &[String]) -> u32 {
+    lowest_total_risk(&to_grid(lines))
+}
+
+fn part2(lines: &[String]) -> u32 {
+    lowest_total_risk(&tile(&to_grid(lines), 5))
+}
+
+fn to_grid(lines: &[String]) -> Vec<Vec<u32>> {
+    lines
+        .iter()
+        .map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect())
+        .collect()
+}
+
+// Repeats the cave times x times in each direction. Every tile to the right or below is one more
+// risky than the last, wrapping from 9 back round to 1.
+fn tile(grid: &[Vec<u32>], times: usize) -> Vec<Vec<u32>> {
+    let (rows, cols) = (grid.len(), grid[0].len());
+    (0..rows * times)
+        .map(|i| {
+            (0..cols * times)
+                .map(|j| {
+                    let risk = grid[i % rows][j % cols] + (i / rows + j / cols) as u32;
+                    (risk - 1) % 9 + 1
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Dijkstra from the top left to the bottom right. The starting position's risk isn't counted
+// since it's never entered.
+fn lowest_total_risk(grid: &[Vec<u32>]) -> u32 {
+    let (rows, cols) = (grid.len(), grid[0].len());
+    let mut best = vec![vec![u32::MAX; cols]; rows];
+    let mut queue = BinaryHeap::new();
+    best[0][0] = 0;
+    queue.push(Reverse((0, 0, 0)));
+    while let Some(Reverse((risk, i, j))) = queue.pop() {
+        if (i, j) == (rows - 1, cols - 1) {
+            return risk;
+        }
+        if risk > best[i][j] {
+            continue;
+        }
+        let neighbours = [
+            (i.wrapping_sub(1), j),
+            (i + 1, j),
+            (i, j.wrapping_sub(1)),
+            (i, j + 1),
+        ];
+        for (x, y) in neighbours {
+            if x < rows && y < cols && risk + grid[x][y] < best[x][y] {
+                best[x][y] = risk + grid[x][y];
+                queue.push(Reverse((best[x][y], x, y)));
+            }
+        }
+    }
+    unreachable!()
+}
+
+#[test]
+fn test_part1() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(40, part1(&sample_data));
+}
+
+#[test]
+fn test_part2() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(315, part2(&sample_data));
+}
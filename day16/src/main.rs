@@ -1,258 +1,643 @@
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt;
 use std::fs;
 use std::io;
-use std::io::BufRead;
+use std::io::Read;
 use std::time::Instant;
 
 struct Packet {
+    offset: usize,
     version: u64,
     type_id: u64,
     literal_value: Option<u64>,
     contained_packets: Vec<Packet>,
 }
 
+#[derive(Debug, PartialEq)]
+enum EvalError {
+    UnknownOperator {
+        type_id: u64,
+        offset: usize,
+    },
+    WrongOperandCount {
+        type_id: u64,
+        operands: usize,
+        offset: usize,
+    },
+    EmptyOperator {
+        type_id: u64,
+        offset: usize,
+    },
+    Overflow {
+        type_id: u64,
+        offset: usize,
+    },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::UnknownOperator { type_id, offset } => {
+                write!(f, "unknown operator type {} at bit {}", type_id, offset)
+            }
+            EvalError::WrongOperandCount {
+                type_id,
+                operands,
+                offset,
+            } => write!(
+                f,
+                "operator type {} at bit {} needs exactly 2 operands, found {}",
+                type_id, offset, operands
+            ),
+            EvalError::EmptyOperator { type_id, offset } => {
+                write!(
+                    f,
+                    "operator type {} at bit {} has no operands",
+                    type_id, offset
+                )
+            }
+            EvalError::Overflow { type_id, offset } => {
+                write!(f, "operator type {} at bit {} overflowed", type_id, offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+// Callbacks for a depth-first walk over a packet and everything it contains. enter is called
+// before a packet's sub-packets are visited and exit after, so both pre- and post-order analyses
+// can be written without recursing by hand.
+trait PacketVisitor {
+    fn enter(&mut self, _packet: &Packet, _depth: usize) {}
+    fn exit(&mut self, _packet: &Packet, _depth: usize) {}
+}
+
+#[derive(Default)]
+struct PacketStats {
+    max_depth: usize,
+    literal_count: usize,
+    operator_counts: BTreeMap<u64, usize>,
+}
+
+impl PacketVisitor for PacketStats {
+    fn enter(&mut self, packet: &Packet, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+        if packet.literal_value.is_some() {
+            self.literal_count += 1;
+        } else {
+            *self.operator_counts.entry(packet.type_id).or_insert(0) += 1;
+        }
+    }
+}
+
 impl Packet {
+    fn walk<V: PacketVisitor>(&self, visitor: &mut V) {
+        self.walk_at_depth(visitor, 0);
+    }
+
+    fn walk_at_depth<V: PacketVisitor>(&self, visitor: &mut V, depth: usize) {
+        visitor.enter(self, depth);
+        for packet in &self.contained_packets {
+            packet.walk_at_depth(visitor, depth + 1);
+        }
+        visitor.exit(self, depth);
+    }
+
+    // Bottom-up fold: f is given each packet along with the results already folded from its
+    // sub-packets.
+    fn fold<T, F: FnMut(&Packet, Vec<T>, usize) -> T>(&self, f: &mut F) -> T {
+        self.fold_at_depth(f, 0)
+    }
+
+    fn fold_at_depth<T, F: FnMut(&Packet, Vec<T>, usize) -> T>(
+        &self,
+        f: &mut F,
+        depth: usize,
+    ) -> T {
+        let folded = self
+            .contained_packets
+            .iter()
+            .map(|p| p.fold_at_depth(f, depth + 1))
+            .collect();
+        f(self, folded, depth)
+    }
+
     fn version_sum(&self) -> u64 {
-        self.version
-            + self
-                .contained_packets
-                .iter()
-                .map(|p| p.version_sum())
-                .sum::<u64>()
+        self.fold(&mut |packet, versions: Vec<u64>, _| {
+            packet.version + versions.iter().sum::<u64>()
+        })
     }
 
-    fn eval(&self) -> u64 {
-        self.literal_value.unwrap_or_else(|| match self.type_id {
-            0 => self.contained_packets.iter().map(|p| p.eval()).sum(),
-            1 => self
-                .contained_packets
-                .iter()
-                .map(|p| p.eval())
-                .reduce(|acc, val| acc * val)
-                .unwrap_or(0),
-            2 => self
-                .contained_packets
+    // Rejects malformed operators instead of guessing a value, and computes in u128 so large
+    // sums and products are reported rather than wrapped.
+    fn try_eval(&self) -> Result<u128, EvalError> {
+        self.fold(&mut |packet, operands: Vec<Result<u128, EvalError>>, _| {
+            packet.apply(
+                operands
+                    .into_iter()
+                    .collect::<Result<Vec<u128>, EvalError>>()?,
+            )
+        })
+    }
+
+    fn apply(&self, operands: Vec<u128>) -> Result<u128, EvalError> {
+        if let Some(literal_value) = self.literal_value {
+            return Ok(literal_value as u128);
+        }
+        let overflow = EvalError::Overflow {
+            type_id: self.type_id,
+            offset: self.offset,
+        };
+        match self.type_id {
+            0..=3 if operands.is_empty() => Err(EvalError::EmptyOperator {
+                type_id: self.type_id,
+                offset: self.offset,
+            }),
+            5..=7 if operands.len() != 2 => Err(EvalError::WrongOperandCount {
+                type_id: self.type_id,
+                operands: operands.len(),
+                offset: self.offset,
+            }),
+            0 => operands
                 .iter()
-                .map(|p| p.eval())
-                .reduce(|acc, val| if val < acc { val } else { acc })
-                .unwrap_or(0),
-            3 => self
-                .contained_packets
+                .try_fold(0u128, |acc, val| acc.checked_add(*val))
+                .ok_or(overflow),
+            1 => operands
                 .iter()
-                .map(|p| p.eval())
-                .reduce(|acc, val| if val > acc { val } else { acc })
-                .unwrap_or(0),
-            5 => {
-                if self.contained_packets.first().unwrap().eval()
-                    > self.contained_packets.last().unwrap().eval()
-                {
-                    1
-                } else {
-                    0
+                .try_fold(1u128, |acc, val| acc.checked_mul(*val))
+                .ok_or(overflow),
+            2 => Ok(*operands.iter().min().unwrap()),
+            3 => Ok(*operands.iter().max().unwrap()),
+            5 => Ok((operands[0] > operands[1]) as u128),
+            6 => Ok((operands[0] < operands[1]) as u128),
+            7 => Ok((operands[0] == operands[1]) as u128),
+            _ => Err(EvalError::UnknownOperator {
+                type_id: self.type_id,
+                offset: self.offset,
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum DecodeError {
+    Io(io::Error),
+    InvalidHex { character: char, offset: usize },
+    Truncated { offset: usize },
+    LiteralTooLarge { offset: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Io(e) => write!(f, "failed to read transmission: {}", e),
+            DecodeError::InvalidHex { character, offset } => {
+                write!(f, "invalid hex digit {:?} at bit {}", character, offset)
+            }
+            DecodeError::Truncated { offset } => {
+                write!(f, "transmission ends mid-packet at bit {}", offset)
+            }
+            DecodeError::LiteralTooLarge { offset } => {
+                write!(f, "literal at bit {} does not fit in 64 bits", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+const CHUNK_SIZE: usize = 4096;
+
+// Pulls hex digits from the reader a chunk at a time and hands them out bit by bit, so only the
+// current chunk is ever held in memory. Whitespace (e.g. the trailing newline) is skipped.
+struct BitReader<R: Read> {
+    reader: R,
+    chunk: Vec<u8>,
+    chunk_len: usize,
+    chunk_pos: usize,
+    nibble: u8,
+    nibble_bits: u8,
+    // Bits given back by skip_zeros: this many zeros followed by a single one
+    replay_zeros: usize,
+    replay_one: bool,
+    offset: usize,
+}
+
+impl<R: Read> BitReader<R> {
+    fn new(reader: R) -> Self {
+        BitReader {
+            reader,
+            chunk: vec![0; CHUNK_SIZE],
+            chunk_len: 0,
+            chunk_pos: 0,
+            nibble: 0,
+            nibble_bits: 0,
+            replay_zeros: 0,
+            replay_one: false,
+            offset: 0,
+        }
+    }
+
+    fn next_hex_digit(&mut self) -> Result<Option<u8>, DecodeError> {
+        loop {
+            if self.chunk_pos == self.chunk_len {
+                self.chunk_len = match self.reader.read(&mut self.chunk) {
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                self.chunk_pos = 0;
+                if self.chunk_len == 0 {
+                    return Ok(None);
                 }
             }
-            6 => {
-                if self.contained_packets.first().unwrap().eval()
-                    < self.contained_packets.last().unwrap().eval()
-                {
-                    1
-                } else {
-                    0
+            let c = self.chunk[self.chunk_pos] as char;
+            self.chunk_pos += 1;
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            return match c.to_digit(16) {
+                Some(digit) => Ok(Some(digit as u8)),
+                None => Err(DecodeError::InvalidHex {
+                    character: c,
+                    offset: self.offset,
+                }),
+            };
+        }
+    }
+
+    fn next_bit(&mut self) -> Result<Option<u8>, DecodeError> {
+        if self.replay_zeros > 0 || self.replay_one {
+            self.offset += 1;
+            if self.replay_zeros > 0 {
+                self.replay_zeros -= 1;
+                return Ok(Some(0));
+            }
+            self.replay_one = false;
+            return Ok(Some(1));
+        }
+        if self.nibble_bits == 0 {
+            match self.next_hex_digit()? {
+                Some(digit) => {
+                    self.nibble = digit;
+                    self.nibble_bits = 4;
                 }
+                None => return Ok(None),
             }
-            7 => {
-                if self.contained_packets.first().unwrap().eval()
-                    == self.contained_packets.last().unwrap().eval()
-                {
-                    1
-                } else {
-                    0
+        }
+        self.nibble_bits -= 1;
+        self.offset += 1;
+        Ok(Some((self.nibble >> self.nibble_bits) & 1))
+    }
+
+    fn read_bits(&mut self, count: usize) -> Result<u64, DecodeError> {
+        let mut value = 0;
+        for _ in 0..count {
+            match self.next_bit()? {
+                Some(bit) => value = value << 1 | bit as u64,
+                None => {
+                    return Err(DecodeError::Truncated {
+                        offset: self.offset,
+                    })
                 }
             }
-            _ => 0,
-        })
+        }
+        Ok(value)
+    }
+
+    // Returns true if everything left in the transmission is zero. Otherwise the zeros scanned
+    // (and the one that ended them) are queued to be read again. Only the length of the run is
+    // kept, so arbitrarily long padding doesn't need to be buffered.
+    fn only_zeros_remain(&mut self) -> Result<bool, DecodeError> {
+        let start_offset = self.offset;
+        loop {
+            match self.next_bit()? {
+                None => return Ok(true),
+                Some(0) => continue,
+                Some(_) => {
+                    self.replay_zeros = self.offset - start_offset - 1;
+                    self.replay_one = true;
+                    self.offset = start_offset;
+                    return Ok(false);
+                }
+            }
+        }
+    }
+}
+
+// Emits top-level packets as soon as each one has been read, stopping once only zero padding is
+// left in the transmission.
+struct PacketStream<R: Read> {
+    bits: BitReader<R>,
+    done: bool,
+}
+
+impl<R: Read> PacketStream<R> {
+    fn new(reader: R) -> Self {
+        PacketStream {
+            bits: BitReader::new(reader),
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for PacketStream<R> {
+    type Item = Result<Packet, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // The first packet is always read, even if it's all zeros
+        if self.bits.offset > 0 {
+            match self.bits.only_zeros_remain() {
+                Ok(true) => {
+                    self.done = true;
+                    return None;
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        let packet = read_packet(&mut self.bits);
+        self.done = packet.is_err();
+        Some(packet)
     }
 }
 
 fn main() {
     let now = Instant::now();
     let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
-    let parsed_lines = read_file_to_vec(filename);
-    let part_1_answer = part1(&parsed_lines);
-    println!(
-        "Part 1 ({}ms): {}",
-        now.elapsed().as_millis(),
-        part_1_answer
-    );
-    let part_2_answer = part2(&parsed_lines);
+    let reader: Box<dyn Read> = if filename == "-" {
+        println!("Reading stdin");
+        Box::new(io::stdin().lock())
+    } else {
+        println!("Reading file {}", filename);
+        Box::new(io::BufReader::new(fs::File::open(filename).unwrap()))
+    };
+    // The transmission is read once, packet by packet, so both parts and the stats are gathered
+    // in a single pass
+    let mut stats = PacketStats::default();
+    let (version_sum, value_sum) =
+        stream_totals(reader, &mut stats).unwrap_or_else(|e| panic!("{}", e));
+    println!("Part 1 ({}ms): {}", now.elapsed().as_millis(), version_sum);
+    println!("Part 2 ({}ms): {}", now.elapsed().as_millis(), value_sum);
     println!(
-        "Part 2 ({}ms): {}",
-        now.elapsed().as_millis(),
-        part_2_answer
+        "Max depth {}, {} literals, operator counts by type {:?}",
+        stats.max_depth, stats.literal_count, stats.operator_counts
     );
 }
 
-fn read_file_to_vec(filename: String) -> Vec<String> {
-    println!("Reading file {}", filename);
-    let file = fs::File::open(filename).unwrap();
-    let reader = io::BufReader::new(file);
-    let mut parsed_lines: Vec<String> = Vec::new();
-    for line in reader.lines() {
-        parsed_lines.push(line.unwrap());
-    }
-    parsed_lines
+// The answers for a transmission given as a single line. main streams its input through
+// stream_totals instead, so these are only built for the tests.
+#[cfg(test)]
+fn part1(lines: &[String]) -> u64 {
+    PacketStream::new(lines[0].as_bytes())
+        .map(|packet| packet.unwrap_or_else(|e| panic!("{}", e)).version_sum())
+        .sum()
 }
 
-fn part1(lines: &Vec<String>) -> u64 {
-    let packets = read_packets(&to_binary(lines.first().unwrap()));
-    packets.iter().map(|p| p.version_sum()).sum()
-}
-
-fn part2(lines: &Vec<String>) -> u64 {
-    let packets = read_packets(&to_binary(lines.first().unwrap()));
-    packets.iter().map(|p| p.eval()).sum()
+#[cfg(test)]
+fn part2(lines: &[String]) -> u128 {
+    PacketStream::new(lines[0].as_bytes())
+        .map(|packet| {
+            let packet = packet.unwrap_or_else(|e| panic!("{}", e));
+            packet.try_eval().unwrap_or_else(|e| panic!("{}", e))
+        })
+        .sum()
 }
 
-fn read_packets(outermost_packet: &String) -> Vec<Packet> {
-    let mut current_offset = 0;
-    let mut packets = Vec::new();
-    while current_offset < outermost_packet.len() {
-        let (packet, next_offset) = read_packet(current_offset, &outermost_packet);
-        packets.push(packet);
-        current_offset = next_offset;
-        if outermost_packet
-            .chars()
-            .skip(current_offset)
-            .all(|c| c == '0')
-        {
-            break;
-        }
+// Sums versions and values over a transmission of any length, holding one top-level packet at a
+// time. Each packet is also walked with the visitor.
+fn stream_totals<R: Read, V: PacketVisitor>(
+    reader: R,
+    visitor: &mut V,
+) -> Result<(u64, u128), Box<dyn std::error::Error>> {
+    let mut version_sum = 0;
+    let mut value_sum: u128 = 0;
+    for packet in PacketStream::new(reader) {
+        let packet = packet?;
+        packet.walk(visitor);
+        version_sum += packet.version_sum();
+        value_sum = value_sum
+            .checked_add(packet.try_eval()?)
+            .ok_or(EvalError::Overflow {
+                type_id: packet.type_id,
+                offset: packet.offset,
+            })?;
     }
-    packets
+    Ok((version_sum, value_sum))
 }
 
-fn read_packet(start_offset: usize, outermost_packet: &String) -> (Packet, usize) {
-    let mut current_offset = start_offset;
-    let header = outermost_packet
-        .chars()
-        .skip(current_offset)
-        .take(6)
-        .collect::<String>();
-    let version =
-        u64::from_str_radix(header.chars().take(3).collect::<String>().as_str(), 2).unwrap();
-    let type_id = u64::from_str_radix(
-        header.chars().skip(3).take(3).collect::<String>().as_str(),
-        2,
-    )
-    .unwrap();
-    current_offset += header.len();
+fn read_packet<R: Read>(bits: &mut BitReader<R>) -> Result<Packet, DecodeError> {
+    let start_offset = bits.offset;
+    let version = bits.read_bits(3)?;
+    let type_id = bits.read_bits(3)?;
     match type_id {
         4 => {
-            let mut literal_value = String::new();
+            let mut literal_value: u64 = 0;
             loop {
-                let next_group: Vec<char> = outermost_packet
-                    .chars()
-                    .skip(current_offset)
-                    .take(5)
-                    .collect();
-                literal_value.push_str(next_group.iter().skip(1).collect::<String>().as_str());
-                current_offset += 5;
-                if next_group[0] == '1' {
-                    continue;
-                } else {
+                let next_group = bits.read_bits(5)?;
+                if literal_value.leading_zeros() < 4 {
+                    return Err(DecodeError::LiteralTooLarge {
+                        offset: start_offset,
+                    });
+                }
+                literal_value = literal_value << 4 | (next_group & 0b1111);
+                if next_group & 0b10000 == 0 {
                     break;
                 }
             }
-            return (
-                Packet {
-                    version,
-                    type_id,
-                    literal_value: Some(u64::from_str_radix(&*literal_value, 2).unwrap()),
-                    contained_packets: vec![],
-                },
-                current_offset,
-            );
+            Ok(Packet {
+                offset: start_offset,
+                version,
+                type_id,
+                literal_value: Some(literal_value),
+                contained_packets: vec![],
+            })
         }
         _ => {
-            let length_type_id = outermost_packet
-                .chars()
-                .skip(current_offset)
-                .nth(0)
-                .unwrap();
-            current_offset += 1;
+            let length_type_id = bits.read_bits(1)?;
             let mut contained_packets = Vec::new();
-            if length_type_id == '0' {
-                let remaining_length = u32::from_str_radix(
-                    &*outermost_packet
-                        .chars()
-                        .skip(current_offset)
-                        .take(15)
-                        .collect::<String>(),
-                    2,
-                )
-                .unwrap();
-                current_offset += 15;
-                let end_offset = current_offset + remaining_length as usize;
-                while current_offset < end_offset {
-                    let (packet, next_packet_start) = read_packet(current_offset, outermost_packet);
-                    contained_packets.push(packet);
-                    current_offset = next_packet_start;
+            if length_type_id == 0 {
+                let remaining_length = bits.read_bits(15)?;
+                let end_offset = bits.offset + remaining_length as usize;
+                while bits.offset < end_offset {
+                    contained_packets.push(read_packet(bits)?);
                 }
             } else {
-                let num_contained_packets = u32::from_str_radix(
-                    &*outermost_packet
-                        .chars()
-                        .skip(current_offset)
-                        .take(11)
-                        .collect::<String>(),
-                    2,
-                )
-                .unwrap();
-                current_offset += 11;
+                let num_contained_packets = bits.read_bits(11)?;
                 for _ in 0..num_contained_packets {
-                    let (packet, next_packet_start) = read_packet(current_offset, outermost_packet);
-                    contained_packets.push(packet);
-                    current_offset = next_packet_start;
+                    contained_packets.push(read_packet(bits)?);
                 }
             }
-            return (
-                Packet {
-                    version,
-                    type_id,
-                    literal_value: None,
-                    contained_packets,
-                },
-                current_offset,
-            );
+            Ok(Packet {
+                offset: start_offset,
+                version,
+                type_id,
+                literal_value: None,
+                contained_packets,
+            })
         }
     }
 }
 
-fn to_binary(hex: &String) -> String {
-    hex.chars()
-        .map(|c| format!("{:04b}", u8::from_str_radix(&*c.to_string(), 16).unwrap()))
-        .collect()
+#[test]
+fn test_part1() {
+    assert_eq!(16, part1(&["8A004A801A8002F478".to_string()]));
+    assert_eq!(12, part1(&["620080001611562C8802118E34".to_string()]));
+    assert_eq!(23, part1(&["C0015000016115A2E0802F182340".to_string()]));
+    assert_eq!(31, part1(&["A0016C880162017C3686B18A3D4780".to_string()]));
 }
 
 #[test]
-fn test_part1() {
-    assert_eq!(16, part1(&vec!["8A004A801A8002F478".to_string()]));
-    assert_eq!(12, part1(&vec!["620080001611562C8802118E34".to_string()]));
-    assert_eq!(23, part1(&vec!["C0015000016115A2E0802F182340".to_string()]));
+fn test_part2() {
+    assert_eq!(3, part2(&["C200B40A82".to_string()]));
+    assert_eq!(54, part2(&["04005AC33890".to_string()]));
+    assert_eq!(7, part2(&["880086C3E88112".to_string()]));
+    assert_eq!(9, part2(&["CE00C43D881120".to_string()]));
+    assert_eq!(1, part2(&["D8005AC2A8F0".to_string()]));
+    assert_eq!(0, part2(&["F600BC2D8F".to_string()]));
+    assert_eq!(0, part2(&["9C005AC2F8F0".to_string()]));
+    assert_eq!(1, part2(&["9C0141080250320F1802104A08".to_string()]));
+}
+
+#[test]
+fn test_try_eval() {
+    let eval = |hex: &str| {
+        PacketStream::new(hex.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap()
+            .try_eval()
+    };
+    assert_eq!(Ok(1), eval("9C0141080250320F1802104A08"));
+    assert_eq!(
+        Ok(2 * u64::MAX as u128),
+        eval("020084FFFFFFFFFFFFFFFFFFEF13FFFFFFFFFFFFFFFFFFBC")
+    );
+    assert_eq!(
+        Err(EvalError::WrongOperandCount {
+            type_id: 5,
+            operands: 3,
+            offset: 0
+        }),
+        eval("1600C40882106")
+    );
+    assert_eq!(
+        Err(EvalError::EmptyOperator {
+            type_id: 0,
+            offset: 0
+        }),
+        eval("02000")
+    );
+    assert_eq!(
+        Err(EvalError::EmptyOperator {
+            type_id: 1,
+            offset: 29
+        }),
+        eval("020084083000")
+    );
     assert_eq!(
-        31,
-        part1(&vec!["A0016C880162017C3686B18A3D4780".to_string()])
+        Err(EvalError::Overflow {
+            type_id: 1,
+            offset: 0
+        }),
+        eval("0600C4FFFFFFFFFFFFFFFFFFEF13FFFFFFFFFFFFFFFFFFBC4FFFFFFFFFFFFFFFFFFEF")
+    );
+    let unknown = Packet {
+        offset: 0,
+        version: 0,
+        type_id: 8,
+        literal_value: None,
+        contained_packets: vec![],
+    };
+    assert_eq!(
+        Err(EvalError::UnknownOperator {
+            type_id: 8,
+            offset: 0
+        }),
+        unknown.try_eval()
     );
 }
 
 #[test]
-fn test_part2() {
-    assert_eq!(3, part2(&vec!["C200B40A82".to_string()]));
-    assert_eq!(54, part2(&vec!["04005AC33890".to_string()]));
-    assert_eq!(7, part2(&vec!["880086C3E88112".to_string()]));
-    assert_eq!(9, part2(&vec!["CE00C43D881120".to_string()]));
-    assert_eq!(1, part2(&vec!["D8005AC2A8F0".to_string()]));
-    assert_eq!(0, part2(&vec!["F600BC2D8F".to_string()]));
-    assert_eq!(0, part2(&vec!["9C005AC2F8F0".to_string()]));
-    assert_eq!(1, part2(&vec!["9C0141080250320F1802104A08".to_string()]));
+fn test_stream_totals() {
+    // F222 is a 16 bit literal packet with version 7 and value 18, so it repeats without padding
+    let transmission = "F222".repeat(10_000) + "000\n";
+    assert_eq!(
+        (70_000, 180_000),
+        stream_totals(transmission.as_bytes(), &mut PacketStats::default()).unwrap()
+    );
+    // Packets split across reads
+    let split = "F2"
+        .as_bytes()
+        .chain("22F".as_bytes())
+        .chain("222".as_bytes());
+    assert_eq!(
+        (14, 36),
+        stream_totals(split, &mut PacketStats::default()).unwrap()
+    );
+    assert_eq!(
+        54,
+        stream_totals("04005AC33890".as_bytes(), &mut PacketStats::default())
+            .unwrap()
+            .1
+    );
+}
+
+#[test]
+fn test_stream_errors() {
+    let first_error = |hex: &str| {
+        PacketStream::new(hex.as_bytes())
+            .find_map(|p| p.err())
+            .map(|e| e.to_string())
+    };
+    assert_eq!(None, first_error("F222F2220000"));
+    assert_eq!(
+        Some("transmission ends mid-packet at bit 12".to_string()),
+        first_error("F22")
+    );
+    assert_eq!(
+        Some("transmission ends mid-packet at bit 24".to_string()),
+        first_error("F2220F")
+    );
+    assert_eq!(
+        Some("invalid hex digit 'G' at bit 16".to_string()),
+        first_error("F222G")
+    );
+    assert_eq!(
+        Some("literal at bit 0 does not fit in 64 bits".to_string()),
+        first_error("F3FFFFFFFFFFFFFFFFFFFFFF")
+    );
+}
+
+#[test]
+fn test_packet_stats() {
+    let packet_stats = |hex: &str| {
+        let mut stats = PacketStats::default();
+        stream_totals(hex.as_bytes(), &mut stats).unwrap();
+        stats
+    };
+    let stats = packet_stats("9C0141080250320F1802104A08");
+    assert_eq!(2, stats.max_depth);
+    assert_eq!(4, stats.literal_count);
+    assert_eq!(
+        BTreeMap::from([(0, 1), (1, 1), (7, 1)]),
+        stats.operator_counts
+    );
+    let stats = packet_stats("A0016C880162017C3686B18A3D4780");
+    assert_eq!(3, stats.max_depth);
+    assert_eq!(5, stats.literal_count);
 }
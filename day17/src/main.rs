@@ -0,0 +1,114 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::time::Instant;
+
+fn main() {
+    let now = Instant::now();
+    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let parsed_lines = read_file_to_vec(filename);
+    let part_1_answer = part1(&parsed_lines);
+    println!(
+        "Part 1 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_1_answer
+    );
+    let part_2_answer = part2(&parsed_lines);
+    println!(
+        "Part 2 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_2_answer
+    );
+}
+
+fn read_file_to_vec(filename: String) -> Vec<String> {
+    println!("Reading file {}", filename);
+    let file = fs::File::open(filename).unwrap();
+    let reader = io::BufReader::new(file);
+    let mut parsed_lines: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        parsed_lines.push(line.unwrap());
+    }
+    parsed_lines
+}
+
+struct Target {
+    x_min: i32,
+    x_max: i32,
+    y_min: i32,
+    y_max: i32,
+}
+
+fn parse_target(line: &str) -> Target {
+    let (x_range, y_range) = line
+        .trim_start_matches("target area: x=")
+        .split_once(", y=")
+        .unwrap();
+    let bounds = |range: &str| {
+        let (min, max) = range.split_once("..").unwrap();
+        (min.parse().unwrap(), max.parse().unwrap())
+    };
+    let (x_min, x_max) = bounds(x_range);
+    let (y_min, y_max) = bounds(y_range);
+    Target {
+        x_min,
+        x_max,
+        y_min,
+        y_max,
+    }
+}
+
+// The highest point reached by the probe if it lands in the target, or None if it misses
+fn launch(target: &Target, mut x_velocity: i32, mut y_velocity: i32) -> Option<i32> {
+    let (mut x, mut y) = (0, 0);
+    let mut highest = 0;
+    // Once the probe is below the target and falling it can never come back up
+    while y >= target.y_min {
+        x += x_velocity;
+        y += y_velocity;
+        x_velocity -= x_velocity.signum();
+        y_velocity -= 1;
+        highest = highest.max(y);
+        if (target.x_min..=target.x_max).contains(&x) && (target.y_min..=target.y_max).contains(&y)
+        {
+            return Some(highest);
+        }
+    }
+    None
+}
+
+// Every launch that hits, as the highest point reached. Assumes the target is to the right and
+// below the launcher: anything faster than the target's far edge overshoots in one step, and a
+// probe launched upwards at v comes back down through y=0 at -(v + 1).
+fn hits(target: &Target) -> Vec<i32> {
+    let mut hits = Vec::new();
+    for x_velocity in 1..=target.x_max {
+        for y_velocity in target.y_min..=-target.y_min {
+            if let Some(highest) = launch(target, x_velocity, y_velocity) {
+                hits.push(highest);
+            }
+        }
+    }
+    hits
+}
+
+fn part1(lines: &[String]) -> i32 {
+    *hits(&parse_target(&lines[0])).iter().max().unwrap()
+}
+
+fn part2(lines: &[String]) -> usize {
+    hits(&parse_target(&lines[0])).len()
+}
+
+#[test]
+fn test_part1() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(45, part1(&sample_data));
+}
+
+#[test]
+fn test_part2() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(112, part2(&sample_data));
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::time::Instant;
+
+fn main() {
+    let now = Instant::now();
+    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let parsed_lines = read_file_to_vec(filename);
+    let part_1_answer = part1(&parsed_lines);
+    println!(
+        "Part 1 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_1_answer
+    );
+    let part_2_answer = part2(&parsed_lines);
+    println!(
+        "Part 2 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_2_answer
+    );
+}
+
+fn read_file_to_vec(filename: String) -> Vec<String> {
+    println!("Reading file {}", filename);
+    let file = fs::File::open(filename).unwrap();
+    let reader = io::BufReader::new(file);
+    let mut parsed_lines: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        parsed_lines.push(line.unwrap());
+    }
+    parsed_lines
+}
+
+type Point = [i32; 3];
+type Rotation = [[i32; 3]; 3];
+
+fn parse_scanners(lines: &[String]) -> Vec<Vec<Point>> {
+    let mut scanners = Vec::new();
+    for line in lines {
+        if line.starts_with("---") {
+            scanners.push(Vec::new());
+        } else if !line.is_empty() {
+            let mut coords = line.split(',').map(|c| c.parse().unwrap());
+            let beacon = [
+                coords.next().unwrap(),
+                coords.next().unwrap(),
+                coords.next().unwrap(),
+            ];
+            scanners.last_mut().unwrap().push(beacon);
+        }
+    }
+    scanners
+}
+
+// The 24 ways a scanner can be facing: every signed permutation of the axes that keeps the
+// coordinate system right-handed
+fn rotations() -> Vec<Rotation> {
+    let permutations = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut rotations = Vec::new();
+    for permutation in permutations {
+        for signs in 0..8 {
+            let mut rotation = [[0; 3]; 3];
+            for (row, axis) in permutation.iter().enumerate() {
+                rotation[row][*axis] = if signs >> row & 1 == 1 { -1 } else { 1 };
+            }
+            if determinant(&rotation) == 1 {
+                rotations.push(rotation);
+            }
+        }
+    }
+    rotations
+}
+
+fn determinant(m: &Rotation) -> i32 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+fn rotate(rotation: &Rotation, point: &Point) -> Point {
+    let mut rotated = [0; 3];
+    for (i, row) in rotation.iter().enumerate() {
+        rotated[i] = row.iter().zip(point).map(|(a, b)| a * b).sum();
+    }
+    rotated
+}
+
+// Finds the rotation and position that puts at least 12 of the scanner's beacons on top of known
+// beacons, returning the scanner's position and its beacons in absolute coordinates
+fn align(
+    known: &[Point],
+    beacons: &[Point],
+    rotations: &[Rotation],
+) -> Option<(Point, Vec<Point>)> {
+    for rotation in rotations {
+        let rotated: Vec<Point> = beacons.iter().map(|b| rotate(rotation, b)).collect();
+        let mut offsets: HashMap<Point, usize> = HashMap::new();
+        for a in known {
+            for b in &rotated {
+                let offset = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+                let count = offsets.entry(offset).or_insert(0);
+                *count += 1;
+                if *count >= 12 {
+                    let absolute = rotated
+                        .iter()
+                        .map(|b| [b[0] + offset[0], b[1] + offset[1], b[2] + offset[2]])
+                        .collect();
+                    return Some((offset, absolute));
+                }
+            }
+        }
+    }
+    None
+}
+
+// Places every scanner relative to scanner 0, returning all the beacons and each scanner's
+// position. Scanners are matched against each newly placed scanner's beacons in turn.
+fn locate(scanners: &[Vec<Point>]) -> (HashSet<Point>, Vec<Point>) {
+    let rotations = rotations();
+    let mut positions: Vec<Option<Point>> = vec![None; scanners.len()];
+    let mut absolute_beacons: Vec<Vec<Point>> = vec![Vec::new(); scanners.len()];
+    positions[0] = Some([0, 0, 0]);
+    absolute_beacons[0] = scanners[0].clone();
+    let mut newly_placed = vec![0];
+    while let Some(placed) = newly_placed.pop() {
+        for i in 0..scanners.len() {
+            if positions[i].is_some() {
+                continue;
+            }
+            if let Some((position, beacons)) =
+                align(&absolute_beacons[placed], &scanners[i], &rotations)
+            {
+                positions[i] = Some(position);
+                absolute_beacons[i] = beacons;
+                newly_placed.push(i);
+            }
+        }
+    }
+    let positions = positions
+        .into_iter()
+        .map(|p| p.expect("scanner doesn't overlap any other"))
+        .collect();
+    (absolute_beacons.into_iter().flatten().collect(), positions)
+}
+
+fn part1(lines: &[String]) -> usize {
+    locate(&parse_scanners(lines)).0.len()
+}
+
+fn part2(lines: &[String]) -> i32 {
+    let (_, positions) = locate(&parse_scanners(lines));
+    let mut furthest = 0;
+    for a in &positions {
+        for b in &positions {
+            furthest = furthest.max((0..3).map(|i| (a[i] - b[i]).abs()).sum());
+        }
+    }
+    furthest
+}
+
+#[test]
+fn test_part1() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(79, part1(&sample_data));
+}
+
+#[test]
+fn test_part2() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(3621, part2(&sample_data));
+}
+
+#[test]
+fn test_rotations() {
+    let rotations = rotations();
+    assert_eq!(24, rotations.len());
+    let facings: HashSet<Point> = rotations.iter().map(|r| rotate(r, &[1, 2, 3])).collect();
+    assert_eq!(24, facings.len());
+}
@@ -1,19 +1,244 @@
 use std::env;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::io::BufRead;
 
-#[derive(Debug)]
-struct Coords {
-    horizontal: u32,
-    depth: u32,
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    Forward(i64),
+    Down(i64),
+    Up(i64),
 }
 
+#[derive(Debug, PartialEq)]
+enum CommandError {
+    UnknownCommand { line: usize, command: String },
+    MissingAmount { line: usize },
+    BadAmount { line: usize, amount: String },
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::UnknownCommand { line, command } => {
+                write!(f, "line {}: unknown command {:?}", line, command)
+            }
+            CommandError::MissingAmount { line } => write!(f, "line {}: missing amount", line),
+            CommandError::BadAmount { line, amount } => {
+                write!(f, "line {}: {:?} is not an amount", line, amount)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl Command {
+    // Parses "<command> <amount>", where line is the 1-based line number used in errors
+    fn parse(line: usize, text: &str) -> Result<Command, CommandError> {
+        let mut parts = text.split_ascii_whitespace();
+        let command = parts.next().unwrap_or("");
+        let amount = parts.next().ok_or(CommandError::MissingAmount { line })?;
+        let amount: i64 = amount.parse().map_err(|_| CommandError::BadAmount {
+            line,
+            amount: amount.to_string(),
+        })?;
+        match command {
+            "forward" => Ok(Command::Forward(amount)),
+            "down" => Ok(Command::Down(amount)),
+            "up" => Ok(Command::Up(amount)),
+            _ => Err(CommandError::UnknownCommand {
+                line,
+                command: command.to_string(),
+            }),
+        }
+    }
+}
+
+fn parse_commands(lines: &[String]) -> Result<Vec<Command>, CommandError> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| Command::parse(i + 1, line))
+        .collect()
+}
+
+// Depth grows downwards, so going up from the surface gives a negative depth rather than an
+// underflow.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Position {
+    horizontal: i64,
+    depth: i64,
+    aim: i64,
+}
+
+// How the submarine moves in response to a command
+trait Interpreter {
+    fn apply(&self, position: Position, command: Command) -> Position;
+}
+
+// Part 1: up and down change depth directly
+struct Direct;
+
+impl Interpreter for Direct {
+    fn apply(&self, position: Position, command: Command) -> Position {
+        match command {
+            Command::Forward(amount) => Position {
+                horizontal: position.horizontal + amount,
+                ..position
+            },
+            Command::Down(amount) => Position {
+                depth: position.depth + amount,
+                ..position
+            },
+            Command::Up(amount) => Position {
+                depth: position.depth - amount,
+                ..position
+            },
+        }
+    }
+}
+
+// Part 2: up and down change aim, and moving forward changes depth by aim * amount
+struct Aimed;
+
+impl Interpreter for Aimed {
+    fn apply(&self, position: Position, command: Command) -> Position {
+        match command {
+            Command::Forward(amount) => Position {
+                horizontal: position.horizontal + amount,
+                depth: position.depth + position.aim * amount,
+                ..position
+            },
+            Command::Down(amount) => Position {
+                aim: position.aim + amount,
+                ..position
+            },
+            Command::Up(amount) => Position {
+                aim: position.aim - amount,
+                ..position
+            },
+        }
+    }
+}
+
+// The position after each command in turn
+fn trace(interpreter: &impl Interpreter, commands: &[Command]) -> Vec<Position> {
+    commands
+        .iter()
+        .scan(Position::default(), |position, command| {
+            *position = interpreter.apply(*position, *command);
+            Some(*position)
+        })
+        .collect()
+}
+
+fn run(interpreter: &impl Interpreter, commands: &[Command]) -> Position {
+    trace(interpreter, commands)
+        .last()
+        .copied()
+        .unwrap_or_default()
+}
+
+// The course taken under each interpretation, starting from the surface
+fn courses(commands: &[Command]) -> Vec<(&'static str, Vec<Position>)> {
+    let with_start = |mut positions: Vec<Position>| {
+        positions.insert(0, Position::default());
+        positions
+    };
+    vec![
+        ("direct", with_start(trace(&Direct, commands))),
+        ("aimed", with_start(trace(&Aimed, commands))),
+    ]
+}
+
+fn to_csv(courses: &[(&str, Vec<Position>)]) -> String {
+    let mut csv = String::from("mode,step,horizontal,depth,aim\n");
+    for (mode, positions) in courses {
+        for (step, position) in positions.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                mode, step, position.horizontal, position.depth, position.aim
+            ));
+        }
+    }
+    csv
+}
+
+// One polyline per course, with depth increasing down the page. The two models end up at very
+// different depths, so each axis is stretched to fit the picture rather than kept to scale.
+fn to_svg(courses: &[(&str, Vec<Position>)]) -> String {
+    const WIDTH: f64 = 800.0;
+    const HEIGHT: f64 = 600.0;
+    const COLOURS: [&str; 2] = ["steelblue", "darkorange"];
+    let all = courses.iter().flat_map(|(_, positions)| positions);
+    let max_horizontal = all.clone().map(|p| p.horizontal).max().unwrap_or(0);
+    let min_horizontal = all.clone().map(|p| p.horizontal).min().unwrap_or(0);
+    let max_depth = all.clone().map(|p| p.depth).max().unwrap_or(0);
+    let min_depth = all.map(|p| p.depth).min().unwrap_or(0);
+    let scale_x = WIDTH / (max_horizontal - min_horizontal).max(1) as f64;
+    let scale_y = HEIGHT / (max_depth - min_depth).max(1) as f64;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        WIDTH, HEIGHT
+    );
+    for (i, (mode, positions)) in courses.iter().enumerate() {
+        let points: Vec<String> = positions
+            .iter()
+            .map(|p| {
+                format!(
+                    "{:.1},{:.1}",
+                    (p.horizontal - min_horizontal) as f64 * scale_x,
+                    (p.depth - min_depth) as f64 * scale_y
+                )
+            })
+            .collect();
+        svg.push_str(&format!(
+            "  <polyline id=\"{}\" fill=\"none\" stroke=\"{}\" points=\"{}\"/>\n",
+            mode,
+            COLOURS[i % COLOURS.len()],
+            points.join(" ")
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Usage: day2 [file] [--trace] [--csv FILE] [--svg FILE]
+// --csv and --svg write the course under both interpretations.
 fn main() {
-    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let mut filename = String::from("input.txt");
+    let mut show_trace = false;
+    let mut csv_file = None;
+    let mut svg_file = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => show_trace = true,
+            "--csv" => csv_file = Some(args.next().expect("--csv FILE")),
+            "--svg" => svg_file = Some(args.next().expect("--svg FILE")),
+            _ => filename = arg,
+        }
+    }
     let parsed_lines = read_file_to_vec(filename);
-    let part_1_answer = part1(&parsed_lines);
-    let part_2_answer = part2(&parsed_lines);
+    let commands = parse_commands(&parsed_lines).unwrap_or_else(|e| panic!("{}", e));
+    if show_trace {
+        print_trace("Part 1", &Direct, &commands);
+        print_trace("Part 2", &Aimed, &commands);
+    }
+    if let Some(csv_file) = csv_file {
+        fs::write(&csv_file, to_csv(&courses(&commands))).unwrap();
+        println!("Wrote {}", csv_file);
+    }
+    if let Some(svg_file) = svg_file {
+        fs::write(&svg_file, to_svg(&courses(&commands))).unwrap();
+        println!("Wrote {}", svg_file);
+    }
+    let part_1_answer = part1(&commands);
+    let part_2_answer = part2(&commands);
     println!(
         "Part 1: {:?} -> {}",
         part_1_answer,
@@ -26,51 +251,121 @@ fn main() {
     );
 }
 
+fn print_trace(label: &str, interpreter: &impl Interpreter, commands: &[Command]) {
+    println!("{}:", label);
+    for (command, position) in commands.iter().zip(trace(interpreter, commands)) {
+        println!(
+            "  {:?} -> horizontal {}, depth {}, aim {}",
+            command, position.horizontal, position.depth, position.aim
+        );
+    }
+}
+
 fn read_file_to_vec(filename: String) -> Vec<String> {
     println!("Reading file {}", filename);
     let file = fs::File::open(filename).unwrap();
     let reader = io::BufReader::new(file);
     let mut parsed_lines: Vec<String> = Vec::new();
-    for (_, line) in reader.lines().enumerate() {
+    for line in reader.lines() {
         parsed_lines.push(line.unwrap());
     }
     parsed_lines
 }
 
-fn part1(lines: &Vec<String>) -> Coords {
-    let mut horizontal = 0;
-    let mut depth = 0;
-    for line in lines {
-        let mut parts = line.split(" ");
-        let command = parts.next().unwrap();
-        let amount: u32 = parts.next().unwrap().parse().unwrap();
-        match command {
-            "forward" => horizontal = horizontal + amount,
-            "down" => depth = depth + amount,
-            "up" => depth = depth - amount,
-            _ => {}
-        }
-    }
-    Coords { horizontal, depth }
+fn part1(commands: &[Command]) -> Position {
+    run(&Direct, commands)
 }
 
-fn part2(lines: &Vec<String>) -> Coords {
-    let mut horizontal: u32 = 0;
-    let mut depth: u32 = 0;
-    let mut aim: u32 = 0;
-    for line in lines {
-        let mut parts = line.split(" ");
-        let command = parts.next().unwrap();
-        let amount: u32 = parts.next().unwrap().parse().unwrap();
-        match command {
-            "forward" => {
-                horizontal = horizontal + amount;
-                depth = depth + aim * amount;
-            }
-            "down" => aim = aim + amount,
-            "up" => aim = aim - amount,
-            _ => {}
-        }
-    }
-    Coords { horizontal, depth }
+fn part2(commands: &[Command]) -> Position {
+    run(&Aimed, commands)
+}
+
+#[test]
+fn test_part1() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let position = part1(&parse_commands(&sample_data).unwrap());
+    assert_eq!(150, position.horizontal * position.depth);
+}
+
+#[test]
+fn test_part2() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let position = part2(&parse_commands(&sample_data).unwrap());
+    assert_eq!(900, position.horizontal * position.depth);
+}
+
+#[test]
+fn test_commands() {
+    let lines = |text: &str| text.lines().map(String::from).collect::<Vec<String>>();
+    assert_eq!(
+        Ok(vec![Command::Up(3), Command::Forward(2)]),
+        parse_commands(&lines("up 3\n\nforward 2"))
+    );
+    assert_eq!(
+        Err(CommandError::UnknownCommand {
+            line: 2,
+            command: String::from("backward"),
+        }),
+        parse_commands(&lines("up 3\nbackward 2"))
+    );
+    assert_eq!(
+        Err(CommandError::MissingAmount { line: 1 }),
+        parse_commands(&lines("down"))
+    );
+    assert_eq!(
+        Err(CommandError::BadAmount {
+            line: 1,
+            amount: String::from("x"),
+        }),
+        parse_commands(&lines("down x"))
+    );
+
+    // Going up from the surface is above it rather than an overflow
+    let commands = [Command::Up(3), Command::Forward(2)];
+    assert_eq!(
+        vec![
+            Position {
+                horizontal: 0,
+                depth: -3,
+                aim: 0
+            },
+            Position {
+                horizontal: 2,
+                depth: -3,
+                aim: 0
+            },
+        ],
+        trace(&Direct, &commands)
+    );
+    assert_eq!(
+        Position {
+            horizontal: 2,
+            depth: -6,
+            aim: -3
+        },
+        run(&Aimed, &commands)
+    );
+}
+
+#[test]
+fn test_export() {
+    let commands = [Command::Forward(2), Command::Down(1), Command::Forward(2)];
+    assert_eq!(
+        "mode,step,horizontal,depth,aim\n\
+         direct,0,0,0,0\n\
+         direct,1,2,0,0\n\
+         direct,2,2,1,0\n\
+         direct,3,4,1,0\n\
+         aimed,0,0,0,0\n\
+         aimed,1,2,0,0\n\
+         aimed,2,2,0,1\n\
+         aimed,3,4,2,1\n",
+        to_csv(&courses(&commands))
+    );
+    let svg = to_svg(&courses(&commands));
+    assert!(svg.contains(
+        "<polyline id=\"direct\" fill=\"none\" stroke=\"steelblue\" \
+         points=\"0.0,0.0 400.0,0.0 400.0,300.0 800.0,300.0\"/>"
+    ));
+    assert!(svg.contains("points=\"0.0,0.0 400.0,0.0 400.0,0.0 800.0,600.0\""));
 }
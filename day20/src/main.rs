@@ -1,25 +1,433 @@
+use automaton::{Automaton, Boundary, Neighbourhood};
+use rayon::prelude::*;
 use std::env;
 use std::fs;
 use std::io;
 use std::io::BufRead;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Instant;
 
+// The 512-entry enhancement algorithm packed into a lookup table of bits
+struct Algorithm([u64; 8]);
+
+impl Algorithm {
+    fn parse(line: &str) -> Algorithm {
+        let mut table = [0; 8];
+        for (i, c) in line.chars().enumerate() {
+            if c == '#' {
+                table[i / 64] |= 1 << (i % 64);
+            }
+        }
+        Algorithm(table)
+    }
+
+    fn lookup(&self, index: usize) -> bool {
+        self.0[index / 64] >> (index % 64) & 1 == 1
+    }
+}
+
+// An image on an infinite canvas: pixels outside the stored region all share the background
+// colour. Enhancing grows the stored region by one pixel on every side and works out the next
+// background from the algorithm, so a lit index 0 flips an unlit background and vice versa.
+// Each row is packed 64 pixels to a word, lowest bit first.
+#[derive(Clone, Debug, PartialEq)]
+struct InfiniteImage {
+    width: usize,
+    rows: Vec<Vec<u64>>,
+    background: bool,
+}
+
+impl InfiniteImage {
+    fn parse(lines: &[String]) -> InfiniteImage {
+        let cells = lines
+            .iter()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect();
+        InfiniteImage::from_cells(cells, false)
+    }
+
+    fn from_cells(cells: Vec<Vec<bool>>, background: bool) -> InfiniteImage {
+        let width = cells.first().map_or(0, |row| row.len());
+        InfiniteImage {
+            width,
+            rows: cells
+                .iter()
+                .map(|row| pack_row(width, |col| row[col]))
+                .collect(),
+            background,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn is_lit(&self, row: isize, col: isize) -> bool {
+        if row < 0 || row as usize >= self.height() {
+            self.background
+        } else {
+            self.is_lit_in_row(&self.rows[row as usize], col)
+        }
+    }
+
+    fn is_lit_in_row(&self, row: &[u64], col: isize) -> bool {
+        if col < 0 || col as usize >= self.width {
+            self.background
+        } else {
+            row[col as usize / 64] >> (col as usize % 64) & 1 == 1
+        }
+    }
+
+    fn next_background(&self, algo: &Algorithm) -> bool {
+        algo.lookup(if self.background { 511 } else { 0 })
+    }
+
+    // Same result as enhance, but much faster. Output rows are independent, so they're computed in
+    // parallel. Within a row each input row contributes a 3-bit window that slides one column at a
+    // time, so every output pixel costs a few shifts and one table lookup.
+    fn enhance_packed(&self, algo: &Algorithm) -> InfiniteImage {
+        // Pad two background rows and columns on every side so the windows never go out of range
+        let background_row = vec![if self.background { u64::MAX } else { 0 }; words(self.width)];
+        let padded: Vec<Vec<u64>> = (0..self.height() + 4)
+            .map(
+                |i| match i.checked_sub(2).and_then(|row| self.rows.get(row)) {
+                    Some(row) => self.pad_row(row),
+                    None => self.pad_row(&background_row),
+                },
+            )
+            .collect();
+        let width = self.width + 2;
+        let rows = padded
+            .par_windows(3)
+            .map(|input_rows| {
+                // Output column j is centred on padded column j + 1
+                let mut windows = [0, 1, 2].map(|r| {
+                    let first_word = input_rows[r][0];
+                    (first_word << 1 & 0b10 | first_word >> 1 & 1) as usize
+                });
+                let mut row = vec![0; words(width)];
+                for j in 0..width {
+                    let col = j + 2;
+                    let mut index = 0;
+                    for (window, input_row) in windows.iter_mut().zip(input_rows) {
+                        let bit = (input_row[col / 64] >> (col % 64) & 1) as usize;
+                        *window = (*window << 1 | bit) & 0b111;
+                        index = index << 3 | *window;
+                    }
+                    row[j / 64] |= (algo.lookup(index) as u64) << (j % 64);
+                }
+                row
+            })
+            .collect();
+        InfiniteImage {
+            width,
+            rows,
+            background: self.next_background(algo),
+        }
+    }
+
+    // Shifts a row two columns right, filling the two columns either side with the background
+    fn pad_row(&self, row: &[u64]) -> Vec<u64> {
+        let padded_width = self.width + 4;
+        let mut padded = vec![0; words(padded_width)];
+        for (k, word) in padded.iter_mut().enumerate() {
+            let current = row.get(k).copied().unwrap_or(0);
+            let previous = if k > 0 { row[k - 1] >> 62 } else { 0 };
+            *word = current << 2 | previous;
+        }
+        if self.background {
+            for col in [0, 1, padded_width - 2, padded_width - 1] {
+                padded[col / 64] |= 1 << (col % 64);
+            }
+        }
+        padded
+    }
+
+    // One enhancement is a step of a cellular automaton where each pixel's 3x3 block is read as a
+    // 9-bit index into the algorithm
+    fn enhance(&self, algo: &Algorithm) -> InfiniteImage {
+        let mut automaton = Automaton::new(
+            self.crop(0, self.height(), self.width),
+            Neighbourhood::Index3x3,
+            Boundary::Infinite(self.background),
+            |_, block: &[bool]| {
+                algo.lookup(
+                    block
+                        .iter()
+                        .fold(0, |index, lit| index << 1 | *lit as usize),
+                )
+            },
+        );
+        automaton.step();
+        let background = automaton.background().unwrap();
+        InfiniteImage::from_cells(automaton.into_cells(), background)
+    }
+
+    // Straightforward pixel-by-pixel enhancement, kept as the baseline for bench
+    fn enhance_pixelwise(&self, algo: &Algorithm) -> InfiniteImage {
+        let width = self.width + 2;
+        let rows = (-1..=self.height() as isize)
+            .map(|i| {
+                pack_row(width, |col| {
+                    let j = col as isize - 1;
+                    let mut index = 0;
+                    for x in i - 1..=i + 1 {
+                        for y in j - 1..=j + 1 {
+                            index = index << 1 | self.is_lit(x, y) as usize;
+                        }
+                    }
+                    algo.lookup(index)
+                })
+            })
+            .collect();
+        InfiniteImage {
+            width,
+            rows,
+            background: self.next_background(algo),
+        }
+    }
+
+    // A height x width window whose top left corner is at (offset, offset) in the stored region,
+    // with the background filling anything outside it
+    fn crop(&self, offset: isize, height: usize, width: usize) -> Vec<Vec<bool>> {
+        (0..height as isize)
+            .map(|i| {
+                (0..width as isize)
+                    .map(|j| self.is_lit(offset + i, offset + j))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // None if the background is lit, since then infinitely many pixels are
+    fn lit_count(&self) -> Option<usize> {
+        if self.background {
+            return None;
+        }
+        Some(
+            self.rows
+                .iter()
+                .flatten()
+                .map(|word| word.count_ones() as usize)
+                .sum(),
+        )
+    }
+}
+
+fn words(width: usize) -> usize {
+    width.div_ceil(64)
+}
+
+fn pack_row<F: Fn(usize) -> bool>(width: usize, is_lit: F) -> Vec<u64> {
+    let mut row = vec![0; words(width)];
+    for col in 0..width {
+        if is_lit(col) {
+            row[col / 64] |= 1 << (col % 64);
+        }
+    }
+    row
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExportFormat {
+    Pbm,
+    Png,
+    // A single animated PNG with one frame per enhancement
+    Apng,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pbm" => Ok(ExportFormat::Pbm),
+            "png" => Ok(ExportFormat::Png),
+            "apng" => Ok(ExportFormat::Apng),
+            _ => Err(format!(
+                "unknown export format {}, expected pbm, png or apng",
+                s
+            )),
+        }
+    }
+}
+
+struct ExportOptions {
+    dir: PathBuf,
+    format: ExportFormat,
+    // Pixels shown on each side of the original image. Defaults to the number of enhancements,
+    // which covers everything that isn't background.
+    margin: Option<usize>,
+}
+
+struct Options {
+    filename: String,
+    bench: bool,
+    export: Option<ExportOptions>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Options {
+    let mut filename = None;
+    let mut bench = false;
+    let mut export_dir = None;
+    let mut format = ExportFormat::Pbm;
+    let mut margin = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bench" => bench = true,
+            "--export" => export_dir = Some(PathBuf::from(args.next().expect("--export DIR"))),
+            "--format" => format = args.next().expect("--format FORMAT").parse().unwrap(),
+            "--crop" => margin = Some(args.next().expect("--crop MARGIN").parse().unwrap()),
+            _ => filename = Some(arg),
+        }
+    }
+    Options {
+        filename: filename.unwrap_or(String::from("input.txt")),
+        bench,
+        export: export_dir.map(|dir| ExportOptions {
+            dir,
+            format,
+            margin,
+        }),
+    }
+}
+
 fn main() {
     let now = Instant::now();
-    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
-    let parsed_lines = read_file_to_vec(filename);
+    let options = parse_args(env::args().skip(1));
+    let parsed_lines = read_file_to_vec(options.filename);
     let part_1_answer = part1(&parsed_lines);
     println!(
         "Part 1 ({}ms): {}",
         now.elapsed().as_millis(),
         part_1_answer
     );
-    let part_2_answer = part2(&parsed_lines, 50, |i| i % 2 == 0);
+    let part_2_answer = part2(&parsed_lines, 50);
     println!(
         "Part 2 ({}ms): {}",
         now.elapsed().as_millis(),
         part_2_answer
     );
+    if options.bench {
+        bench(&parsed_lines, 50);
+    }
+    if let Some(export) = options.export {
+        export_frames(&parsed_lines, 50, &export).unwrap();
+    }
+}
+
+// Writes the image before and after each enhancement, cropped to the same window around the
+// original image so the frames line up.
+fn export_frames(
+    lines: &[String],
+    num_enhancements: u32,
+    options: &ExportOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let algo = Algorithm::parse(&lines[0]);
+    let mut image = InfiniteImage::parse(&lines[2..]);
+    let margin = options.margin.unwrap_or(num_enhancements as usize);
+    let (height, width) = (image.height() + 2 * margin, image.width + 2 * margin);
+    fs::create_dir_all(&options.dir)?;
+    let mut frames = Vec::new();
+    for step in 0..=num_enhancements as usize {
+        // The stored region grows by one pixel on each side per enhancement
+        let frame = image.crop(step as isize - margin as isize, height, width);
+        match options.format {
+            ExportFormat::Pbm => {
+                let path = options.dir.join(format!("step_{:03}.pbm", step));
+                fs::write(path, to_pbm(&frame))?;
+            }
+            ExportFormat::Png => {
+                let path = options.dir.join(format!("step_{:03}.png", step));
+                write_png(fs::File::create(path)?, &[frame])?;
+            }
+            ExportFormat::Apng => frames.push(frame),
+        }
+        image = image.enhance_packed(&algo);
+    }
+    if options.format == ExportFormat::Apng {
+        write_png(fs::File::create(options.dir.join("enhance.png"))?, &frames)?;
+    }
+    println!(
+        "Wrote {} frames of {}x{} to {}",
+        num_enhancements + 1,
+        width,
+        height,
+        options.dir.display()
+    );
+    Ok(())
+}
+
+// Plain (ASCII) PBM, where 1 is black. Lit pixels are drawn black.
+fn to_pbm(frame: &[Vec<bool>]) -> String {
+    let width = frame.first().map_or(0, |row| row.len());
+    let mut pbm = format!("P1\n{} {}\n", width, frame.len());
+    for row in frame {
+        let pixels: Vec<&str> = row.iter().map(|lit| if *lit { "1" } else { "0" }).collect();
+        pbm.push_str(&pixels.join(" "));
+        pbm.push('\n');
+    }
+    pbm
+}
+
+// 1-bit greyscale PNG, lit pixels black to match the PBM output. More than one frame makes an
+// animated PNG, shown at 10 frames per second.
+fn write_png<W: Write>(out: W, frames: &[Vec<Vec<bool>>]) -> Result<(), png::EncodingError> {
+    let height = frames[0].len();
+    let width = frames[0].first().map_or(0, |row| row.len());
+    let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::One);
+    if frames.len() > 1 {
+        encoder.set_animated(frames.len() as u32, 0)?;
+        encoder.set_frame_delay(1, 10)?;
+    }
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        let mut data = Vec::with_capacity(height * width.div_ceil(8));
+        for row in frame {
+            for byte in row.chunks(8) {
+                let mut packed = 0xff_u8;
+                for (bit, lit) in byte.iter().enumerate() {
+                    if *lit {
+                        packed &= !(0x80 >> bit);
+                    }
+                }
+                data.push(packed);
+            }
+        }
+        writer.write_image_data(&data)?;
+    }
+    writer.finish()
+}
+
+// Times the pixel-by-pixel baseline, the generic automaton version and the packed, parallel one
+fn bench(lines: &[String], num_enhancements: u32) {
+    let algo = Algorithm::parse(&lines[0]);
+    let image = InfiniteImage::parse(&lines[2..]);
+    let timed = |enhance: fn(&InfiniteImage, &Algorithm) -> InfiniteImage| {
+        let now = Instant::now();
+        let mut enhanced = image.clone();
+        for _ in 0..num_enhancements {
+            enhanced = enhance(&enhanced, &algo);
+        }
+        (now.elapsed(), enhanced)
+    };
+    let (pixelwise_time, pixelwise) = timed(InfiniteImage::enhance_pixelwise);
+    let (automaton_time, automaton) = timed(InfiniteImage::enhance);
+    let (packed_time, packed) = timed(InfiniteImage::enhance_packed);
+    assert_eq!(pixelwise, automaton);
+    assert_eq!(pixelwise, packed);
+    println!(
+        "{} enhancements: pixelwise {}ms, automaton {}ms, packed {}ms ({:.1}x faster than pixelwise)",
+        num_enhancements,
+        pixelwise_time.as_millis(),
+        automaton_time.as_millis(),
+        packed_time.as_millis(),
+        pixelwise_time.as_secs_f64() / packed_time.as_secs_f64()
+    );
 }
 
 fn read_file_to_vec(filename: String) -> Vec<String> {
@@ -33,118 +441,19 @@ fn read_file_to_vec(filename: String) -> Vec<String> {
     parsed_lines
 }
 
-fn part1(lines: &Vec<String>) -> usize {
-    let image_enhancement_algo = &lines[0].replace('.', "0").replace('#', "1");
-    let mut input_image: Vec<String> = Vec::new();
-    for line in &lines[2..] {
-        input_image.push(line.clone());
-    }
-    let output_image = enhance(image_enhancement_algo, &input_image, false);
-    // println!("Input");
-    // for line in &input_image {
-    //     println!("{}", line);
-    // }
-    // println!("Output");
-    // for line in &output_image {
-    //     println!("{}", line);
-    // }
-    // println!("Output (2)");
-    let twice_ehanced = &enhance(image_enhancement_algo, &output_image, true);
-    // for line in twice_ehanced {
-    //     println!("{}", line);
-    // }
-    twice_ehanced
-        .iter()
-        .map(|l| l.chars().filter(|c| *c == '#').count())
-        .sum()
-}
-
-fn enhance(
-    image_enhancement_algo: &String,
-    input_image: &Vec<String>,
-    assume_infinite_lit: bool,
-) -> Vec<String> {
-    let mut output_image: Vec<String> = Vec::new();
-    for i in -1..=input_image.len() as isize {
-        let mut current_row = String::new();
-        for j in -1..=input_image[0].len() as isize {
-            if is_lit_after_enhance(
-                (i, j),
-                image_enhancement_algo,
-                &input_image,
-                assume_infinite_lit,
-            ) {
-                current_row.push('#');
-            } else {
-                current_row.push('.');
-            }
-        }
-        output_image.push(current_row);
-    }
-    output_image
-}
-
-fn is_lit_after_enhance(
-    coords: (isize, isize),
-    algo: &String,
-    input_image: &Vec<String>,
-    assume_infinite_lit: bool,
-) -> bool {
-    let total_rows = input_image.len();
-    let total_cols = input_image[0].len();
-    let (i, j) = coords;
-    let mut output_binary = if assume_infinite_lit {
-        "111111111".to_string()
-    } else {
-        "000000000".to_string()
-    };
-    let mut binary_index = 0;
-    for x in vec![i - 1, i, i + 1] {
-        for y in vec![j - 1, j, j + 1] {
-            if x < 0 || y < 0 {
-                binary_index += 1;
-                continue;
-            }
-            let x_usize = x as usize;
-            let y_usize = y as usize;
-            if x_usize < total_rows && y_usize < total_cols {
-                if input_image[x_usize].chars().nth(y_usize).unwrap() == '#' {
-                    output_binary.replace_range(binary_index..=binary_index, "1");
-                } else {
-                    output_binary.replace_range(binary_index..=binary_index, "0");
-                }
-            }
-            binary_index += 1;
-        }
-    }
-    algo.chars()
-        .nth(usize::from_str_radix(&output_binary, 2).unwrap())
-        .unwrap()
-        == '1'
+fn part1(lines: &[String]) -> usize {
+    part2(lines, 2)
 }
 
-fn part2(
-    lines: &Vec<String>,
-    num_enhancements: u32,
-    assume_infinite_lit: fn(u32) -> bool,
-) -> usize {
-    let image_enhancement_algo = &lines[0].replace('.', "0").replace('#', "1");
-    let mut input_image: Vec<String> = Vec::new();
-    for line in &lines[2..] {
-        input_image.push(line.clone());
-    }
-    let mut output_image = input_image.clone();
-    for i in 1..=num_enhancements {
-        output_image = enhance(
-            image_enhancement_algo,
-            &output_image,
-            assume_infinite_lit(i),
-        );
+fn part2(lines: &[String], num_enhancements: u32) -> usize {
+    let image_enhancement_algo = Algorithm::parse(&lines[0]);
+    let mut image = InfiniteImage::parse(&lines[2..]);
+    for _ in 0..num_enhancements {
+        image = image.enhance_packed(&image_enhancement_algo);
     }
-    output_image
-        .iter()
-        .map(|l| l.chars().filter(|c| *c == '#').count())
-        .sum()
+    image
+        .lit_count()
+        .expect("background is lit, so infinitely many pixels are")
 }
 
 #[test]
@@ -156,5 +465,89 @@ fn test_part1() {
 #[test]
 fn test_part2() {
     let sample_data = read_file_to_vec(String::from("test.txt"));
-    assert_eq!(3351, part2(&sample_data, 50, |_| false));
+    assert_eq!(3351, part2(&sample_data, 50));
+}
+
+#[test]
+fn test_background_flips() {
+    // Index 0 lit and index 511 unlit, like the real inputs
+    let algo = Algorithm::parse(&format!("#{}", ".".repeat(511)));
+    let image = InfiniteImage::parse(&["#".to_string()]);
+    let once = image.enhance(&algo);
+    assert!(once.background);
+    assert_eq!(None, once.lit_count());
+    let twice = once.enhance(&algo);
+    assert!(!twice.background);
+    // Only the centre pixel sees nothing but unlit pixels
+    assert_eq!(Some(1), twice.lit_count());
+    let unlit = Algorithm::parse(&".".repeat(512));
+    assert!(!InfiniteImage::parse(&[]).enhance(&unlit).background);
+}
+
+#[test]
+fn test_enhance_packed_matches_automaton() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let flipping = format!("#{}.", &sample_data[0][1..511]);
+    for algo in [&sample_data[0], &flipping] {
+        let algo = Algorithm::parse(algo);
+        // Wide enough to span several words per row
+        let mut packed = InfiniteImage::parse(&sample_data[2..]);
+        let mut automaton = packed.clone();
+        let mut pixelwise = packed.clone();
+        for _ in 0..70 {
+            packed = packed.enhance_packed(&algo);
+            automaton = automaton.enhance(&algo);
+            pixelwise = pixelwise.enhance_pixelwise(&algo);
+            assert_eq!(automaton, packed);
+            assert_eq!(pixelwise, packed);
+        }
+    }
+}
+
+#[test]
+fn test_crop_and_pbm() {
+    let image = InfiniteImage::parse(&["#.".to_string(), ".#".to_string()]);
+    assert_eq!(
+        "P1\n4 3\n0 0 0 0\n0 1 0 0\n0 0 1 0\n",
+        to_pbm(&image.crop(-1, 3, 4))
+    );
+    let lit_background = image.enhance(&Algorithm::parse(&"#".repeat(512)));
+    assert_eq!(vec![vec![true; 2]; 2], lit_background.crop(-5, 2, 2));
+}
+
+#[test]
+fn test_export_frames() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let dir = env::temp_dir().join(format!("day20_export_{}", std::process::id()));
+    for format in [ExportFormat::Pbm, ExportFormat::Png, ExportFormat::Apng] {
+        let options = ExportOptions {
+            dir: dir.clone(),
+            format,
+            margin: Some(3),
+        };
+        export_frames(&sample_data, 2, &options).unwrap();
+    }
+    let pbm = fs::read_to_string(dir.join("step_002.pbm")).unwrap();
+    assert!(pbm.starts_with("P1\n11 11\n"));
+    assert_eq!(
+        35,
+        pbm.lines()
+            .skip(2)
+            .flat_map(|l| l.split(' '))
+            .filter(|p| *p == "1")
+            .count()
+    );
+
+    let png = png::Decoder::new(fs::File::open(dir.join("step_000.png")).unwrap());
+    let mut reader = png.read_info().unwrap();
+    assert_eq!((11, 11), (reader.info().width, reader.info().height));
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    // Row 3 is the first row of the original image, #..#. offset by the 3 pixel margin
+    assert_eq!([0b1110_1101, 0b1111_1111], data[6..8]);
+
+    let apng = png::Decoder::new(fs::File::open(dir.join("enhance.png")).unwrap());
+    let reader = apng.read_info().unwrap();
+    assert_eq!(3, reader.info().animation_control.unwrap().num_frames);
+    fs::remove_dir_all(dir).unwrap();
 }
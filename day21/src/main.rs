@@ -1,12 +1,31 @@
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::io::BufRead;
 use std::time::Instant;
 
+// Both parts are played on the puzzle's board of squares 1 to 10
+const BOARD_SIZE: u32 = 10;
+
+// Usage: day21 [file] [--seed N] [--table] [--csv]
+// --seed also plays part 1 with a random die, and --table/--csv print the Dirac dice win table.
 fn main() {
     let now = Instant::now();
-    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let mut filename = String::from("input.txt");
+    let mut seed = None;
+    let mut table = false;
+    let mut csv = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => seed = Some(args.next().expect("--seed N").parse().unwrap()),
+            "--table" => table = true,
+            "--csv" => csv = true,
+            _ => filename = arg,
+        }
+    }
     let parsed_lines = read_file_to_vec(filename);
     let part_1_answer = part1(&parsed_lines);
     println!(
@@ -20,6 +39,18 @@ fn main() {
         now.elapsed().as_millis(),
         part_2_answer
     );
+    if let Some(seed) = seed {
+        let result = seeded_game(&parsed_lines, seed);
+        println!(
+            "Seeded game: player {} wins after {} rolls with scores {:?}",
+            result.winner + 1,
+            result.rolls,
+            result.scores
+        );
+    }
+    if table || csv {
+        print_win_table(&dirac_win_table(), csv);
+    }
 }
 
 fn read_file_to_vec(filename: String) -> Vec<String> {
@@ -33,48 +64,419 @@ fn read_file_to_vec(filename: String) -> Vec<String> {
     parsed_lines
 }
 
-fn part1(lines: &Vec<String>) -> u32 {
-    let mut player_1_pos = lines[0].chars().last().unwrap().to_digit(10).unwrap();
-    let mut player_2_pos = lines[1].chars().last().unwrap().to_digit(10).unwrap();
-    let mut dice = 1;
-    let mut player_1_score = 0;
-    let mut player_2_score = 0;
-    let mut rolls = 0;
-    loop {
-        for _ in 0..3 {
-            player_1_pos += dice;
-            dice = dice % 100 + 1;
-            rolls += 1;
+trait Die {
+    // Faces the next roll can land on, each with the number of universes it lands that way in.
+    // An ordinary die lands on exactly one face, in one universe.
+    fn roll(&mut self) -> Vec<(u32, u64)>;
+}
+
+// Rolls 1, 2, 3, ... up to sides, then starts over at 1
+struct DeterministicDie {
+    sides: u32,
+    next: u32,
+}
+
+impl DeterministicDie {
+    fn new(sides: u32) -> DeterministicDie {
+        DeterministicDie { sides, next: 1 }
+    }
+}
+
+impl Die for DeterministicDie {
+    fn roll(&mut self) -> Vec<(u32, u64)> {
+        let face = self.next;
+        self.next = self.next % self.sides + 1;
+        vec![(face, 1)]
+    }
+}
+
+// Pseudo-random rolls from an xorshift generator, so games with the same seed replay exactly
+struct SeededDie {
+    sides: u32,
+    state: u64,
+}
+
+impl SeededDie {
+    fn new(sides: u32, seed: u64) -> SeededDie {
+        // xorshift gets stuck on 0
+        SeededDie {
+            sides,
+            state: seed.max(1),
         }
-        let current_score = player_1_pos % 10;
-        player_1_score += if current_score == 0 {
-            10
-        } else {
-            current_score
-        };
-        if player_1_score >= 1000 {
-            return rolls * player_2_score;
+    }
+}
+
+impl Die for SeededDie {
+    fn roll(&mut self) -> Vec<(u32, u64)> {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        vec![((self.state % self.sides as u64) as u32 + 1, 1)]
+    }
+}
+
+// Every roll splits the universe, one copy for each face
+struct DiracDie {
+    sides: u32,
+}
+
+impl Die for DiracDie {
+    fn roll(&mut self) -> Vec<(u32, u64)> {
+        (1..=self.sides).map(|face| (face, 1)).collect()
+    }
+}
+
+struct Game {
+    board_size: u32,
+    target_score: u32,
+    rolls_per_turn: u32,
+    starting_positions: Vec<u32>,
+}
+
+struct GameResult {
+    scores: Vec<u32>,
+    winner: usize,
+    rolls: u32,
+}
+
+type GameState = (Vec<u32>, Vec<u32>, usize);
+
+impl Game {
+    fn advance(&self, position: u32, steps: u32) -> u32 {
+        (position + steps - 1) % self.board_size + 1
+    }
+
+    // Plays a single universe to the end, so the die must only ever land one way
+    fn play<D: Die>(&self, die: &mut D) -> GameResult {
+        let mut positions = self.starting_positions.clone();
+        let mut scores = vec![0; positions.len()];
+        let mut rolls = 0;
+        for player in (0..positions.len()).cycle() {
+            let mut steps = 0;
+            for _ in 0..self.rolls_per_turn {
+                match die.roll()[..] {
+                    [(face, _)] => steps += face,
+                    _ => panic!("play follows one universe, use count_wins for quantum dice"),
+                }
+                rolls += 1;
+            }
+            positions[player] = self.advance(positions[player], steps);
+            scores[player] += positions[player];
+            if scores[player] >= self.target_score {
+                return GameResult {
+                    scores,
+                    winner: player,
+                    rolls,
+                };
+            }
         }
+        unreachable!()
+    }
+
+    // Number of universes each player wins in. The die is rolled rolls_per_turn times up front to
+    // find how many universes each turn total happens in, so it has to land the same way every
+    // time, like a DiracDie.
+    fn count_wins<D: Die>(&self, die: &mut D) -> Vec<u64> {
+        let players = self.starting_positions.len();
+        self.count_wins_from(
+            &self.turn_totals(die),
+            (self.starting_positions.clone(), vec![0; players], 0),
+            &mut HashMap::new(),
+        )
+    }
 
-        for _ in 0..3 {
-            player_2_pos += dice;
-            dice = dice % 100 + 1;
-            rolls += 1;
+    // How many universes each total of a turn's rolls happens in
+    fn turn_totals<D: Die>(&self, die: &mut D) -> Vec<(u32, u64)> {
+        let mut turn_totals = HashMap::from([(0, 1)]);
+        for _ in 0..self.rolls_per_turn {
+            let outcomes = die.roll();
+            let mut next_totals = HashMap::new();
+            for (total, universes) in turn_totals {
+                for (face, face_universes) in &outcomes {
+                    *next_totals.entry(total + face).or_insert(0) += universes * face_universes;
+                }
+            }
+            turn_totals = next_totals;
         }
-        let current_score = player_2_pos % 10;
-        player_2_score += if current_score == 0 {
-            10
-        } else {
-            current_score
+        turn_totals.into_iter().collect()
+    }
+
+    fn count_wins_from(
+        &self,
+        turn_totals: &[(u32, u64)],
+        state: GameState,
+        cache: &mut HashMap<GameState, Vec<u64>>,
+    ) -> Vec<u64> {
+        if let Some(wins) = cache.get(&state) {
+            return wins.clone();
+        }
+        let (positions, scores, to_move) = &state;
+        let mut wins = vec![0; positions.len()];
+        for (steps, universes) in turn_totals {
+            let mut next_positions = positions.clone();
+            let mut next_scores = scores.clone();
+            next_positions[*to_move] = self.advance(positions[*to_move], *steps);
+            next_scores[*to_move] += next_positions[*to_move];
+            if next_scores[*to_move] >= self.target_score {
+                wins[*to_move] += universes;
+            } else {
+                let next_state = (next_positions, next_scores, (to_move + 1) % positions.len());
+                let later_wins = self.count_wins_from(turn_totals, next_state, cache);
+                for (total, later) in wins.iter_mut().zip(later_wins) {
+                    *total += universes * later;
+                }
+            }
+        }
+        cache.insert(state, wins.clone());
+        wins
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum StartPositionError {
+    Malformed {
+        line_number: usize,
+        line: String,
+    },
+    OutOfOrder {
+        line_number: usize,
+        expected: usize,
+        found: usize,
+    },
+    OffBoard {
+        line_number: usize,
+        position: u32,
+    },
+    NoPlayers,
+    TooFewPlayers {
+        found: usize,
+    },
+}
+
+impl fmt::Display for StartPositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StartPositionError::Malformed { line_number, line } => write!(
+                f,
+                "line {}: expected \"Player N starting position: P\", got {:?}",
+                line_number, line
+            ),
+            StartPositionError::OutOfOrder {
+                line_number,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected player {}, got player {}",
+                line_number, expected, found
+            ),
+            StartPositionError::OffBoard {
+                line_number,
+                position,
+            } => write!(
+                f,
+                "line {}: starting position {} is not on the board",
+                line_number, position
+            ),
+            StartPositionError::NoPlayers => write!(f, "no players found"),
+            StartPositionError::TooFewPlayers { found } => {
+                write!(f, "need at least 2 players, found {}", found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StartPositionError {}
+
+// Reads "Player N starting position: P" lines, one per player in order, with each P from 1 to
+// board_size. Blank lines are ignored.
+fn parse_starting_positions(
+    lines: &[String],
+    board_size: u32,
+) -> Result<Vec<u32>, StartPositionError> {
+    let mut positions = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let malformed = || StartPositionError::Malformed {
+            line_number,
+            line: line.to_string(),
         };
-        if player_2_score >= 1000 {
-            return rolls * player_1_score;
+        let (player, position) = line
+            .strip_prefix("Player ")
+            .and_then(|rest| rest.split_once(" starting position:"))
+            .ok_or_else(malformed)?;
+        let player: usize = player.trim().parse().map_err(|_| malformed())?;
+        let position: u32 = position.trim().parse().map_err(|_| malformed())?;
+        if player != positions.len() + 1 {
+            return Err(StartPositionError::OutOfOrder {
+                line_number,
+                expected: positions.len() + 1,
+                found: player,
+            });
+        }
+        if position == 0 || position > board_size {
+            return Err(StartPositionError::OffBoard {
+                line_number,
+                position,
+            });
         }
+        positions.push(position);
+    }
+    if positions.is_empty() {
+        return Err(StartPositionError::NoPlayers);
     }
+    Ok(positions)
 }
 
-fn part2(lines: &Vec<String>) -> usize {
-    lines.len()
+fn starting_positions(lines: &[String], board_size: u32) -> Vec<u32> {
+    parse_starting_positions(lines, board_size).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// Part 1 scores the players who lost, so there has to be at least one besides the winner
+fn rival_starting_positions(
+    lines: &[String],
+    board_size: u32,
+) -> Result<Vec<u32>, StartPositionError> {
+    let positions = parse_starting_positions(lines, board_size)?;
+    if positions.len() < 2 {
+        return Err(StartPositionError::TooFewPlayers {
+            found: positions.len(),
+        });
+    }
+    Ok(positions)
+}
+
+fn part1(lines: &[String]) -> u32 {
+    let game = Game {
+        board_size: BOARD_SIZE,
+        target_score: 1000,
+        rolls_per_turn: 3,
+        starting_positions: rival_starting_positions(lines, BOARD_SIZE)
+            .unwrap_or_else(|e| panic!("{}", e)),
+    };
+    let result = game.play(&mut DeterministicDie::new(100));
+    let losing_score = (0..result.scores.len())
+        .filter(|player| *player != result.winner)
+        .map(|player| result.scores[player])
+        .min()
+        .unwrap();
+    result.rolls * losing_score
+}
+
+// Part 1's game, but with a random 100-sided die
+fn seeded_game(lines: &[String], seed: u64) -> GameResult {
+    let game = Game {
+        board_size: BOARD_SIZE,
+        target_score: 1000,
+        rolls_per_turn: 3,
+        starting_positions: starting_positions(lines, BOARD_SIZE),
+    };
+    game.play(&mut SeededDie::new(100, seed))
+}
+
+fn part2(lines: &[String]) -> u64 {
+    *dirac_wins(starting_positions(lines, BOARD_SIZE))
+        .iter()
+        .max()
+        .unwrap()
+}
+
+struct WinCounts {
+    player_1_start: u32,
+    player_2_start: u32,
+    wins: Vec<u64>,
+}
+
+impl WinCounts {
+    // The fraction of universes player 1 wins in
+    fn player_1_probability(&self) -> f64 {
+        self.wins[0] as f64 / (self.wins[0] + self.wins[1]) as f64
+    }
+}
+
+// Dirac dice wins for every pair of starting positions. The games only differ in where they
+// start, so they share one cache.
+fn dirac_win_table() -> Vec<WinCounts> {
+    let game = Game {
+        board_size: BOARD_SIZE,
+        target_score: 21,
+        rolls_per_turn: 3,
+        starting_positions: vec![],
+    };
+    let turn_totals = game.turn_totals(&mut DiracDie { sides: 3 });
+    let mut cache = HashMap::new();
+    let mut table = Vec::new();
+    for player_1_start in 1..=game.board_size {
+        for player_2_start in 1..=game.board_size {
+            let state = (vec![player_1_start, player_2_start], vec![0, 0], 0);
+            table.push(WinCounts {
+                player_1_start,
+                player_2_start,
+                wins: game.count_wins_from(&turn_totals, state, &mut cache),
+            });
+        }
+    }
+    table
+}
+
+fn print_win_table(table: &[WinCounts], csv: bool) {
+    if csv {
+        println!("player_1_start,player_2_start,player_1_wins,player_2_wins,player_1_probability");
+        for counts in table {
+            println!(
+                "{},{},{},{},{}",
+                counts.player_1_start,
+                counts.player_2_start,
+                counts.wins[0],
+                counts.wins[1],
+                counts.player_1_probability()
+            );
+        }
+        return;
+    }
+    let print_grid = |title: &str, width: usize, cell: &dyn Fn(&WinCounts) -> String| {
+        println!(
+            "{}, by player 1 start (rows) and player 2 start (columns)",
+            title
+        );
+        print!("    ");
+        for player_2_start in 1..=10 {
+            print!("{:>width$}", player_2_start, width = width);
+        }
+        println!();
+        for row in table.chunks(10) {
+            print!("{:>4}", row[0].player_1_start);
+            for counts in row {
+                print!("{:>width$}", cell(counts), width = width);
+            }
+            println!();
+        }
+    };
+    print_grid("Chance of player 1 winning", 7, &|counts| {
+        format!("{:.3}", counts.player_1_probability())
+    });
+    println!();
+    print_grid("Universes player 1 wins in", 17, &|counts| {
+        counts.wins[0].to_string()
+    });
+    println!();
+    print_grid("Universes player 2 wins in", 17, &|counts| {
+        counts.wins[1].to_string()
+    });
+}
+
+fn dirac_wins(starting_positions: Vec<u32>) -> Vec<u64> {
+    let game = Game {
+        board_size: BOARD_SIZE,
+        target_score: 21,
+        rolls_per_turn: 3,
+        starting_positions,
+    };
+    game.count_wins(&mut DiracDie { sides: 3 })
 }
 
 #[test]
@@ -86,5 +488,145 @@ fn test_part1() {
 #[test]
 fn test_part2() {
     let sample_data = read_file_to_vec(String::from("test.txt"));
-    assert_eq!(0, part2(&sample_data));
+    assert_eq!(444356092776315, part2(&sample_data));
+    assert_eq!(
+        vec![444356092776315, 341960390180808],
+        dirac_wins(vec![4, 8])
+    );
+}
+
+#[test]
+fn test_game_variants() {
+    // Three players, a smaller board and a single roll per turn
+    let game = Game {
+        board_size: 5,
+        target_score: 10,
+        rolls_per_turn: 1,
+        starting_positions: vec![1, 2, 3],
+    };
+    let result = game.play(&mut DeterministicDie::new(6));
+    // Player 2 rolls 2, 5, 2, 5 landing on 4, 4, 1, 1 and reaches 10 on the 11th roll
+    assert_eq!(vec![6, 10, 8], result.scores);
+    assert_eq!(1, result.winner);
+    assert_eq!(11, result.rolls);
+
+    let seeded = |seed| {
+        let result = game.play(&mut SeededDie::new(6, seed));
+        (result.scores, result.winner, result.rolls)
+    };
+    assert_eq!(seeded(42), seeded(42));
+    let (scores, winner, _) = seeded(7);
+    assert!(scores[winner] >= 10);
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let result = seeded_game(&sample_data, 2021);
+    assert!(result.scores[result.winner] >= 1000);
+    assert!(result.scores[1 - result.winner] < 1000);
+
+    // A 1-sided quantum die only ever makes one universe, so it plays like a deterministic die
+    let wins = game.count_wins(&mut DiracDie { sides: 1 });
+    let result = game.play(&mut DeterministicDie::new(1));
+    let mut expected = vec![0; 3];
+    expected[result.winner] = 1;
+    assert_eq!(expected, wins);
+}
+
+#[test]
+fn test_parse_starting_positions() {
+    let lines = |text: &str| text.lines().map(String::from).collect::<Vec<String>>();
+    assert_eq!(
+        Ok(vec![10, 3, 10]),
+        parse_starting_positions(
+            &lines(
+                "Player 1 starting position: 10  \nPlayer 2 starting position: 3\nPlayer 3 starting position:10\n\n"
+            ),
+            10
+        )
+    );
+    assert_eq!(
+        Err(StartPositionError::Malformed {
+            line_number: 2,
+            line: "Player 2 starting position: ten".to_string()
+        }),
+        parse_starting_positions(
+            &lines("Player 1 starting position: 1\nPlayer 2 starting position: ten"),
+            10
+        )
+    );
+    assert_eq!(
+        Err(StartPositionError::OutOfOrder {
+            line_number: 1,
+            expected: 1,
+            found: 2
+        }),
+        parse_starting_positions(&lines("Player 2 starting position: 1"), 10)
+    );
+    assert_eq!(
+        Err(StartPositionError::OffBoard {
+            line_number: 1,
+            position: 0
+        }),
+        parse_starting_positions(&lines("Player 1 starting position: 0"), 10)
+    );
+    assert_eq!(
+        Err(StartPositionError::OffBoard {
+            line_number: 2,
+            position: 57
+        }),
+        parse_starting_positions(
+            &lines("Player 1 starting position: 10\nPlayer 2 starting position: 57"),
+            10
+        )
+    );
+    assert_eq!(
+        Ok(vec![57]),
+        parse_starting_positions(&lines("Player 1 starting position: 57"), 100)
+    );
+    assert_eq!(
+        Err(StartPositionError::NoPlayers),
+        parse_starting_positions(&lines("\n"), 10)
+    );
+    assert_eq!(
+        Err(StartPositionError::TooFewPlayers { found: 1 }),
+        rival_starting_positions(&lines("Player 1 starting position: 4"), 10)
+    );
+    assert_eq!(
+        Ok(vec![4, 8]),
+        rival_starting_positions(
+            &lines("Player 1 starting position: 4\nPlayer 2 starting position: 8"),
+            10
+        )
+    );
+}
+
+#[test]
+fn test_start_position_10() {
+    let sample_data = vec![
+        "Player 1 starting position: 10".to_string(),
+        "Player 2 starting position: 10".to_string(),
+    ];
+    assert_eq!(vec![10, 10], starting_positions(&sample_data, BOARD_SIZE));
+    assert_eq!(850080, part1(&sample_data));
+    assert_eq!(104001566545663, part2(&sample_data));
+}
+
+#[test]
+fn test_dirac_win_table() {
+    let table = dirac_win_table();
+    assert_eq!(100, table.len());
+    let counts = &table[3 * 10 + 7];
+    assert_eq!((4, 8), (counts.player_1_start, counts.player_2_start));
+    assert_eq!(vec![444356092776315, 341960390180808], counts.wins);
+    // Sharing the cache between games doesn't change any of them
+    for counts in table.iter().step_by(7) {
+        assert_eq!(
+            dirac_wins(vec![counts.player_1_start, counts.player_2_start]),
+            counts.wins
+        );
+    }
+    // From the same square, going first is an advantage
+    for start in 1..=10 {
+        let counts = &table[(start - 1) * 11];
+        assert_eq!(counts.player_1_start, counts.player_2_start);
+        assert!(counts.player_1_probability() > 0.5);
+    }
 }
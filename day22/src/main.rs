@@ -0,0 +1,175 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::time::Instant;
+
+fn main() {
+    let now = Instant::now();
+    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let parsed_lines = read_file_to_vec(filename);
+    let part_1_answer = part1(&parsed_lines);
+    println!(
+        "Part 1 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_1_answer
+    );
+    let part_2_answer = part2(&parsed_lines);
+    println!(
+        "Part 2 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_2_answer
+    );
+}
+
+fn read_file_to_vec(filename: String) -> Vec<String> {
+    println!("Reading file {}", filename);
+    let file = fs::File::open(filename).unwrap();
+    let reader = io::BufReader::new(file);
+    let mut parsed_lines: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        parsed_lines.push(line.unwrap());
+    }
+    parsed_lines
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cuboid {
+    min: [i64; 3],
+    max: [i64; 3],
+}
+
+impl Cuboid {
+    fn intersection(&self, other: &Cuboid) -> Option<Cuboid> {
+        let mut min = [0; 3];
+        let mut max = [0; 3];
+        for axis in 0..3 {
+            min[axis] = self.min[axis].max(other.min[axis]);
+            max[axis] = self.max[axis].min(other.max[axis]);
+            if min[axis] > max[axis] {
+                return None;
+            }
+        }
+        Some(Cuboid { min, max })
+    }
+
+    fn volume(&self) -> i64 {
+        (0..3)
+            .map(|axis| self.max[axis] - self.min[axis] + 1)
+            .product()
+    }
+}
+
+fn parse_steps(lines: &[String]) -> Vec<(bool, Cuboid)> {
+    lines
+        .iter()
+        .map(|line| {
+            let (state, ranges) = line.split_once(' ').unwrap();
+            let mut min = [0; 3];
+            let mut max = [0; 3];
+            for (axis, range) in ranges.split(',').enumerate() {
+                let (low, high) = range[2..].split_once("..").unwrap();
+                min[axis] = low.parse().unwrap();
+                max[axis] = high.parse().unwrap();
+            }
+            (state == "on", Cuboid { min, max })
+        })
+        .collect()
+}
+
+// Keeps a list of cuboids with signs, so the cubes lit are the sum of their signed volumes. Each
+// step cancels out its overlap with everything so far (inclusion-exclusion), then adds itself if
+// it's turning cubes on.
+fn cubes_on(steps: &[(bool, Cuboid)]) -> i64 {
+    let mut signed: Vec<(Cuboid, i64)> = Vec::new();
+    for (on, cuboid) in steps {
+        let overlaps: Vec<(Cuboid, i64)> = signed
+            .iter()
+            .filter_map(|(other, sign)| other.intersection(cuboid).map(|i| (i, -sign)))
+            .collect();
+        signed.extend(overlaps);
+        if *on {
+            signed.push((*cuboid, 1));
+        }
+    }
+    signed
+        .iter()
+        .map(|(cuboid, sign)| sign * cuboid.volume())
+        .sum()
+}
+
+fn part1(lines: &[String]) -> i64 {
+    let initialization_region = Cuboid {
+        min: [-50; 3],
+        max: [50; 3],
+    };
+    let steps: Vec<(bool, Cuboid)> = parse_steps(lines)
+        .iter()
+        .filter_map(|(on, cuboid)| {
+            cuboid
+                .intersection(&initialization_region)
+                .map(|c| (*on, c))
+        })
+        .collect();
+    cubes_on(&steps)
+}
+
+fn part2(lines: &[String]) -> i64 {
+    cubes_on(&parse_steps(lines))
+}
+
+#[test]
+fn test_part1() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(39, part1(&sample_data));
+}
+
+#[test]
+fn test_part2() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(39, part2(&sample_data));
+    let sample_data = read_file_to_vec(String::from("test_2.txt"));
+    assert_eq!(474140, part1(&sample_data));
+    assert_eq!(2758514936282235, part2(&sample_data));
+    let outside = vec![
+        "on x=-60..-40,y=0..0,z=0..0".to_string(),
+        "off x=-45..-45,y=0..0,z=0..0".to_string(),
+    ];
+    assert_eq!(10, part1(&outside));
+    assert_eq!(20, part2(&outside));
+}
+
+#[test]
+fn test_cubes_on_matches_brute_force() {
+    // Overlapping steps in a small space, checked cube by cube
+    let mut seed: i64 = 22;
+    let mut next = |range: i64| {
+        seed = (seed * 1103515245 + 12345) % 2147483648;
+        seed % range
+    };
+    let mut steps = Vec::new();
+    for _ in 0..30 {
+        let mut min = [0; 3];
+        let mut max = [0; 3];
+        for axis in 0..3 {
+            min[axis] = next(10) - 5;
+            max[axis] = min[axis] + next(6);
+        }
+        steps.push((next(3) != 0, Cuboid { min, max }));
+    }
+    let mut lit = std::collections::HashSet::new();
+    for (on, cuboid) in &steps {
+        for x in cuboid.min[0]..=cuboid.max[0] {
+            for y in cuboid.min[1]..=cuboid.max[1] {
+                for z in cuboid.min[2]..=cuboid.max[2] {
+                    if *on {
+                        lit.insert((x, y, z));
+                    } else {
+                        lit.remove(&(x, y, z));
+                    }
+                }
+            }
+        }
+    }
+    assert_eq!(lit.len() as i64, cubes_on(&steps));
+}
@@ -0,0 +1,195 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::time::Instant;
+
+fn main() {
+    let now = Instant::now();
+    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let parsed_lines = read_file_to_vec(filename);
+    let part_1_answer = part1(&parsed_lines);
+    println!(
+        "Part 1 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_1_answer
+    );
+    let part_2_answer = part2(&parsed_lines);
+    println!(
+        "Part 2 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_2_answer
+    );
+}
+
+fn read_file_to_vec(filename: String) -> Vec<String> {
+    println!("Reading file {}", filename);
+    let file = fs::File::open(filename).unwrap();
+    let reader = io::BufReader::new(file);
+    let mut parsed_lines: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        parsed_lines.push(line.unwrap());
+    }
+    parsed_lines
+}
+
+const HALLWAY: usize = 11;
+const ENERGY: [u32; 4] = [1, 10, 100, 1000];
+
+// The burrow as a flat list of cells: the hallway, then each room from top to bottom. 0 is an
+// empty cell and 1-4 are amphipods A-D.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Burrow {
+    cells: Vec<u8>,
+    depth: usize,
+}
+
+impl Burrow {
+    fn parse(lines: &[String]) -> Burrow {
+        let room_rows: Vec<Vec<u8>> = lines[2..]
+            .iter()
+            .map(|line| {
+                line.chars()
+                    .filter(|c| c.is_ascii_uppercase())
+                    .map(|c| c as u8 - b'A' + 1)
+                    .collect::<Vec<u8>>()
+            })
+            .filter(|row| !row.is_empty())
+            .collect();
+        let depth = room_rows.len();
+        let mut cells = vec![0; HALLWAY + 4 * depth];
+        for (level, row) in room_rows.iter().enumerate() {
+            for (room, amphipod) in row.iter().enumerate() {
+                cells[HALLWAY + room * depth + level] = *amphipod;
+            }
+        }
+        Burrow { cells, depth }
+    }
+
+    fn room_cell(&self, room: usize, level: usize) -> usize {
+        HALLWAY + room * self.depth + level
+    }
+
+    fn door(room: usize) -> usize {
+        2 + 2 * room
+    }
+
+    fn room(&self, room: usize) -> &[u8] {
+        &self.cells[self.room_cell(room, 0)..self.room_cell(room, self.depth)]
+    }
+
+    // True if only amphipods that belong in the room are in it
+    fn room_is_ready(&self, room: usize) -> bool {
+        self.room(room)
+            .iter()
+            .all(|a| *a == 0 || *a as usize == room + 1)
+    }
+
+    fn is_organized(&self) -> bool {
+        (0..4).all(|room| self.room(room).iter().all(|a| *a as usize == room + 1))
+    }
+
+    fn hallway_clear(&self, from: usize, to: usize) -> bool {
+        let (low, high) = if from < to { (from, to) } else { (to, from) };
+        self.cells[low..=high].iter().all(|a| *a == 0)
+    }
+
+    fn moved(&self, from: usize, to: usize) -> Burrow {
+        let mut next = self.clone();
+        next.cells.swap(from, to);
+        next
+    }
+
+    // Every burrow reachable with one move, with the energy it takes
+    fn moves(&self) -> Vec<(Burrow, u32)> {
+        let mut moves = Vec::new();
+        // Into a room, from the hallway
+        for hall in 0..HALLWAY {
+            let amphipod = self.cells[hall];
+            if amphipod == 0 {
+                continue;
+            }
+            let room = amphipod as usize - 1;
+            let door = Burrow::door(room);
+            let path_start = if hall < door { hall + 1 } else { hall - 1 };
+            if !self.room_is_ready(room) || !self.hallway_clear(path_start, door) {
+                continue;
+            }
+            let level = self.room(room).iter().rposition(|a| *a == 0).unwrap();
+            let steps = hall.abs_diff(door) + level + 1;
+            moves.push((
+                self.moved(hall, self.room_cell(room, level)),
+                steps as u32 * ENERGY[room],
+            ));
+        }
+        // Out of a room, into the hallway
+        for room in 0..4 {
+            if self.room_is_ready(room) {
+                continue;
+            }
+            let level = self.room(room).iter().position(|a| *a != 0).unwrap();
+            let amphipod = self.cells[self.room_cell(room, level)];
+            let door = Burrow::door(room);
+            for hall in [0, 1, 3, 5, 7, 9, 10] {
+                if self.hallway_clear(hall, door) {
+                    let steps = hall.abs_diff(door) + level + 1;
+                    moves.push((
+                        self.moved(self.room_cell(room, level), hall),
+                        steps as u32 * ENERGY[amphipod as usize - 1],
+                    ));
+                }
+            }
+        }
+        moves
+    }
+}
+
+// Dijkstra over burrow states
+fn least_energy(start: Burrow) -> u32 {
+    let mut best = HashMap::from([(start.clone(), 0)]);
+    let mut queue = BinaryHeap::from([Reverse((0, start))]);
+    while let Some(Reverse((energy, burrow))) = queue.pop() {
+        if burrow.is_organized() {
+            return energy;
+        }
+        if energy > best[&burrow] {
+            continue;
+        }
+        for (next, cost) in burrow.moves() {
+            let next_energy = energy + cost;
+            if best.get(&next).is_none_or(|e| next_energy < *e) {
+                best.insert(next.clone(), next_energy);
+                queue.push(Reverse((next_energy, next)));
+            }
+        }
+    }
+    panic!("the amphipods can't be organized")
+}
+
+fn part1(lines: &[String]) -> u32 {
+    least_energy(Burrow::parse(lines))
+}
+
+// The folded-out diagram has two more rows between the two in the input
+fn part2(lines: &[String]) -> u32 {
+    let mut unfolded = lines[..3].to_vec();
+    unfolded.push("  #D#C#B#A#".to_string());
+    unfolded.push("  #D#B#A#C#".to_string());
+    unfolded.extend_from_slice(&lines[3..]);
+    least_energy(Burrow::parse(&unfolded))
+}
+
+#[test]
+fn test_part1() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(12521, part1(&sample_data));
+}
+
+#[test]
+fn test_part2() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(44169, part2(&sample_data));
+}
@@ -0,0 +1,246 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::time::Instant;
+
+fn main() {
+    let now = Instant::now();
+    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let parsed_lines = read_file_to_vec(filename);
+    let part_1_answer = part1(&parsed_lines);
+    println!(
+        "Part 1 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_1_answer
+    );
+    let part_2_answer = part2(&parsed_lines);
+    println!(
+        "Part 2 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_2_answer
+    );
+}
+
+fn read_file_to_vec(filename: String) -> Vec<String> {
+    println!("Reading file {}", filename);
+    let file = fs::File::open(filename).unwrap();
+    let reader = io::BufReader::new(file);
+    let mut parsed_lines: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        parsed_lines.push(line.unwrap());
+    }
+    parsed_lines
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Operand {
+    Register(usize),
+    Number(i64),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Instruction {
+    Inp(usize),
+    Add(usize, Operand),
+    Mul(usize, Operand),
+    Div(usize, Operand),
+    Mod(usize, Operand),
+    Eql(usize, Operand),
+}
+
+fn register(name: &str) -> usize {
+    match name {
+        "w" => 0,
+        "x" => 1,
+        "y" => 2,
+        "z" => 3,
+        _ => panic!("unknown register {}", name),
+    }
+}
+
+fn parse_program(lines: &[String]) -> Vec<Instruction> {
+    lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let parts: Vec<&str> = line.split(' ').collect();
+            let a = register(parts[1]);
+            if parts[0] == "inp" {
+                return Instruction::Inp(a);
+            }
+            let b = match parts[2].parse() {
+                Ok(number) => Operand::Number(number),
+                Err(_) => Operand::Register(register(parts[2])),
+            };
+            match parts[0] {
+                "add" => Instruction::Add(a, b),
+                "mul" => Instruction::Mul(a, b),
+                "div" => Instruction::Div(a, b),
+                "mod" => Instruction::Mod(a, b),
+                "eql" => Instruction::Eql(a, b),
+                _ => panic!("unknown instruction {}", line),
+            }
+        })
+        .collect()
+}
+
+// Runs the program, returning the w, x, y and z registers at the end
+fn run(program: &[Instruction], input: &[i64]) -> [i64; 4] {
+    let mut registers = [0; 4];
+    let mut input = input.iter();
+    for instruction in program {
+        let value = |operand: &Operand, registers: &[i64; 4]| match operand {
+            Operand::Register(r) => registers[*r],
+            Operand::Number(n) => *n,
+        };
+        match instruction {
+            Instruction::Inp(a) => registers[*a] = *input.next().unwrap(),
+            Instruction::Add(a, b) => registers[*a] += value(b, &registers),
+            Instruction::Mul(a, b) => registers[*a] *= value(b, &registers),
+            Instruction::Div(a, b) => registers[*a] /= value(b, &registers),
+            Instruction::Mod(a, b) => registers[*a] %= value(b, &registers),
+            Instruction::Eql(a, b) => {
+                registers[*a] = (registers[*a] == value(b, &registers)) as i64
+            }
+        }
+    }
+    registers
+}
+
+// MONAD is 14 near-identical blocks, one per digit, that treat z as a stack of base 26 digits.
+// Blocks that divide z by 1 always push w + offset. Blocks that divide by 26 pop the top value,
+// and only avoid pushing again if w equals it plus their check. Pairing each pop with its push
+// gives a constraint digit[pop] = digit[push] + offset + check, and the model number is valid if
+// all 7 hold.
+fn digit_constraints(program: &[Instruction]) -> Vec<(usize, usize, i64)> {
+    let number = |instruction: &Instruction| match instruction {
+        Instruction::Div(_, Operand::Number(n)) | Instruction::Add(_, Operand::Number(n)) => *n,
+        _ => panic!("not a MONAD block: {:?}", instruction),
+    };
+    let mut stack = Vec::new();
+    let mut constraints = Vec::new();
+    for (digit, block) in program.chunks(18).enumerate() {
+        if number(&block[4]) == 1 {
+            stack.push((digit, number(&block[15])));
+        } else {
+            let (pushed_digit, offset) = stack.pop().unwrap();
+            constraints.push((pushed_digit, digit, offset + number(&block[5])));
+        }
+    }
+    constraints
+}
+
+// Picks each pair of digits as large (or small) as the constraint between them allows, then
+// double checks the result by running it through MONAD
+fn model_number(program: &[Instruction], largest: bool) -> Vec<i64> {
+    let mut digits = vec![0; 14];
+    for (push, pop, difference) in digit_constraints(program) {
+        digits[push] = if largest {
+            9.min(9 - difference)
+        } else {
+            1.max(1 - difference)
+        };
+        digits[pop] = digits[push] + difference;
+    }
+    assert_eq!(0, run(program, &digits)[3], "MONAD rejected {:?}", digits);
+    digits
+}
+
+fn to_number(digits: &[i64]) -> i64 {
+    digits.iter().fold(0, |number, digit| number * 10 + digit)
+}
+
+fn part1(lines: &[String]) -> i64 {
+    to_number(&model_number(&parse_program(lines), true))
+}
+
+fn part2(lines: &[String]) -> i64 {
+    to_number(&model_number(&parse_program(lines), false))
+}
+
+// The puzzle gives no example MONAD, so test.txt is its 18-instruction block repeated 14 times
+// with hand-picked div z, add x and add y values. The expected answers are checked against a
+// plain search in test_search rather than derived from the constraints.
+#[test]
+fn test_part1() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(19699519499893, part1(&sample_data));
+}
+
+#[test]
+fn test_part2() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(14195116197121, part2(&sample_data));
+    // One past the smallest breaks the constraint on the last digit
+    let mut digits = model_number(&parse_program(&sample_data), false);
+    digits[13] += 1;
+    assert_ne!(0, run(&parse_program(&sample_data), &digits)[3]);
+}
+
+#[test]
+fn test_search() {
+    use std::collections::HashSet;
+
+    // Tries digits one block at a time, running each block on the z left by the ones before it.
+    // Only z carries over between blocks, so a z that failed once at some block always fails
+    // there. A block that divides z by 26 removes at most one base 26 digit, so a z with more
+    // digits than there are of those blocks left can never get back to 0 either.
+    fn search(
+        blocks: &[&[Instruction]],
+        z: i64,
+        digits: &[i64],
+        dead_ends: &mut HashSet<(usize, i64)>,
+    ) -> Option<i64> {
+        let (block, rest) = match blocks.split_first() {
+            Some(split) => split,
+            None => return (z == 0).then_some(0),
+        };
+        let pops_left = blocks
+            .iter()
+            .filter(|block| {
+                block
+                    .iter()
+                    .any(|i| matches!(i, Instruction::Div(3, Operand::Number(26))))
+            })
+            .count();
+        if z >= 26_i64.pow(pops_left as u32) || dead_ends.contains(&(blocks.len(), z)) {
+            return None;
+        }
+        let mut program = vec![Instruction::Add(3, Operand::Number(z))];
+        program.extend_from_slice(block);
+        let found = digits.iter().find_map(|digit| {
+            search(rest, run(&program, &[*digit])[3], digits, dead_ends)
+                .map(|number| digit * 10_i64.pow(rest.len() as u32) + number)
+        });
+        if found.is_none() {
+            dead_ends.insert((blocks.len(), z));
+        }
+        found
+    }
+    let program = parse_program(&read_file_to_vec(String::from("test.txt")));
+    let blocks: Vec<&[Instruction]> = program.chunks(18).collect();
+    let descending: Vec<i64> = (1..=9).rev().collect();
+    let ascending: Vec<i64> = (1..=9).collect();
+    assert_eq!(
+        Some(19699519499893),
+        search(&blocks, 0, &descending, &mut HashSet::new())
+    );
+    assert_eq!(
+        Some(14195116197121),
+        search(&blocks, 0, &ascending, &mut HashSet::new())
+    );
+}
+
+#[test]
+fn test_alu() {
+    // Splits the input into its lowest four bits, from the puzzle description
+    let to_binary: Vec<String> = [
+        "inp w", "add z w", "mod z 2", "div w 2", "add y w", "mod y 2", "div w 2", "add x w",
+        "mod x 2", "div w 2", "mod w 2",
+    ]
+    .iter()
+    .map(|line| line.to_string())
+    .collect();
+    assert_eq!([1, 1, 0, 1], run(&parse_program(&to_binary), &[13]));
+}
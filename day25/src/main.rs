@@ -0,0 +1,68 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::time::Instant;
+
+fn main() {
+    let now = Instant::now();
+    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let parsed_lines = read_file_to_vec(filename);
+    let part_1_answer = part1(&parsed_lines);
+    println!(
+        "Part 1 ({}ms): {}",
+        now.elapsed().as_millis(),
+        part_1_answer
+    );
+}
+
+fn read_file_to_vec(filename: String) -> Vec<String> {
+    println!("Reading file {}", filename);
+    let file = fs::File::open(filename).unwrap();
+    let reader = io::BufReader::new(file);
+    let mut parsed_lines: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        parsed_lines.push(line.unwrap());
+    }
+    parsed_lines
+}
+
+fn to_grid(lines: &[String]) -> Vec<Vec<u8>> {
+    lines.iter().map(|line| line.bytes().collect()).collect()
+}
+
+// Moves every cucumber in the herd that can, all at once, wrapping round the edges. Returns
+// whether any moved.
+fn move_herd(grid: &mut Vec<Vec<u8>>, herd: u8, (down, right): (usize, usize)) -> bool {
+    let (rows, cols) = (grid.len(), grid[0].len());
+    let mut next = grid.clone();
+    let mut moved = false;
+    for i in 0..rows {
+        for j in 0..cols {
+            let (x, y) = ((i + down) % rows, (j + right) % cols);
+            if grid[i][j] == herd && grid[x][y] == b'.' {
+                next[i][j] = b'.';
+                next[x][y] = herd;
+                moved = true;
+            }
+        }
+    }
+    *grid = next;
+    moved
+}
+
+fn part1(lines: &[String]) -> usize {
+    let mut grid = to_grid(lines);
+    let mut steps = 1;
+    // East-facing cucumbers move first, then south-facing ones
+    while move_herd(&mut grid, b'>', (0, 1)) | move_herd(&mut grid, b'v', (1, 0)) {
+        steps += 1;
+    }
+    steps
+}
+
+#[test]
+fn test_part1() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    assert_eq!(58, part1(&sample_data));
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::cmp::Ordering;
 use std::env;
 use std::fs;
 use std::io;
@@ -18,78 +18,157 @@ fn read_file_to_vec(filename: String) -> Vec<String> {
     let file = fs::File::open(filename).unwrap();
     let reader = io::BufReader::new(file);
     let mut parsed_lines: Vec<String> = Vec::new();
-    for (_, line) in reader.lines().enumerate() {
+    for line in reader.lines() {
         parsed_lines.push(line.unwrap());
     }
     parsed_lines
 }
 
-fn part1(lines: &Vec<String>) -> u32 {
-    let mut gamma_rate: u32 = 0;
-    let mut epsilon_rate: u32 = 0;
-    let total_bits = lines[0].len();
-    let mut one_counts: Vec<u32> = vec![0; total_bits];
-    for line in lines {
-        for (i, c) in line.chars().enumerate() {
-            if c == '1' {
-                one_counts[i] += 1;
-            }
+// The report as numbers, along with how many bits wide its lines are
+struct Report {
+    values: Vec<u64>,
+    width: usize,
+}
+
+fn parse_report(lines: &[String]) -> Report {
+    let lines: Vec<&str> = lines
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    assert!(
+        width <= 64,
+        "report lines are {} bits wide, at most 64 are supported",
+        width
+    );
+    Report {
+        values: lines
+            .iter()
+            .map(|l| u64::from_str_radix(l, 2).unwrap())
+            .collect(),
+        width,
+    }
+}
+
+fn bit_at(value: u64, position: usize) -> bool {
+    value >> position & 1 == 1
+}
+
+// Which bit wins when a column has as many ones as zeros
+#[derive(Clone, Copy, Debug)]
+enum TieBreak {
+    One,
+    Zero,
+}
+
+impl TieBreak {
+    fn bit(&self) -> bool {
+        matches!(self, TieBreak::One)
+    }
+}
+
+// How many ones and zeros the report has in each bit position. Positions count from the least
+// significant bit. For bits, 1 is true and 0 is false.
+struct BitColumnStats {
+    one_counts: Vec<usize>,
+    total: usize,
+}
+
+impl BitColumnStats {
+    fn new(values: &[u64], width: usize) -> BitColumnStats {
+        BitColumnStats {
+            one_counts: (0..width)
+                .map(|position| values.iter().filter(|v| bit_at(**v, position)).count())
+                .collect(),
+            total: values.len(),
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.one_counts.len()
+    }
+
+    fn ones(&self, position: usize) -> usize {
+        self.one_counts[position]
+    }
+
+    fn zeros(&self, position: usize) -> usize {
+        self.total - self.one_counts[position]
+    }
+
+    fn most_common(&self, position: usize, tie_break: TieBreak) -> bool {
+        match self.ones(position).cmp(&self.zeros(position)) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => tie_break.bit(),
         }
     }
-    let majority_threshold: u32 = (lines.len() as u32 / 2 as u32) as u32;
-    for (i, count) in one_counts.iter().enumerate() {
-        let bit_position: u32 = (total_bits - i - 1) as u32;
-        if count >= &majority_threshold {
-            gamma_rate += 2_u32.pow(bit_position);
-        } else {
-            epsilon_rate += 2_u32.pow(bit_position);
+
+    fn least_common(&self, position: usize, tie_break: TieBreak) -> bool {
+        match self.ones(position).cmp(&self.zeros(position)) {
+            Ordering::Greater => false,
+            Ordering::Less => true,
+            Ordering::Equal => tie_break.bit(),
+        }
+    }
+}
+
+fn part1(lines: &[String]) -> u128 {
+    let report = parse_report(lines);
+    let stats = BitColumnStats::new(&report.values, report.width);
+    let mut gamma_rate: u64 = 0;
+    let mut epsilon_rate: u64 = 0;
+    for position in 0..stats.width() {
+        if stats.most_common(position, TieBreak::One) {
+            gamma_rate |= 1 << position;
+        }
+        if stats.least_common(position, TieBreak::Zero) {
+            epsilon_rate |= 1 << position;
         }
     }
     println!("gamma = {}, epsilon = {}", gamma_rate, epsilon_rate);
-    gamma_rate * epsilon_rate
+    gamma_rate as u128 * epsilon_rate as u128
 }
 
-fn part2_recursive(lines: &Vec<String>) -> u32 {
-    let oxygen_generator_rating = filter_part_2_recursive(lines.clone(), 0, false);
-    let co2_scrubber_rating = filter_part_2_recursive(lines.clone(), 0, true);
-    oxygen_generator_rating * co2_scrubber_rating
+fn part2(lines: &[String]) -> u128 {
+    let report = parse_report(lines);
+    oxygen_generator_rating(&report) as u128 * co2_scrubber_rating(&report) as u128
 }
 
-fn filter_part_2_recursive(lines: Vec<String>, position: usize, negate: bool) -> u32 {
-    if lines.len() == 1 {
-        return u32::from_str_radix(lines.iter().next().unwrap(), 2).unwrap();
-    }
-    let most_common_bit = if negate {
-        !most_common_bit_at_position(&lines, position)
-    } else {
-        most_common_bit_at_position(&lines, position)
-    };
-    return filter_part_2_recursive(
-        lines
-            .iter()
-            .filter(|l| {
-                let bit_at_pos = l.chars().nth(position).unwrap();
-                if most_common_bit {
-                    bit_at_pos == '1'
-                } else {
-                    bit_at_pos == '0'
-                }
-            })
-            .map(|l| l.clone())
-            .collect(),
-        position + 1,
-        negate,
-    );
+fn oxygen_generator_rating(report: &Report) -> u64 {
+    filter_by_bit_criteria(report, |stats, position| {
+        stats.most_common(position, TieBreak::One)
+    })
 }
 
-// 1 is true, 0 is false
-fn most_common_bit_at_position(lines: &Vec<String>, position: usize) -> bool {
-    let majority_threshold = (lines.len() as f64 / 2_f64).ceil() as usize;
-    lines
-        .iter()
-        .filter(|l| l.chars().nth(position).unwrap() == '1')
-        .count()
-        >= majority_threshold
+fn co2_scrubber_rating(report: &Report) -> u64 {
+    filter_by_bit_criteria(report, |stats, position| {
+        stats.least_common(position, TieBreak::Zero)
+    })
+}
+
+// Keeps the values with the bit picked by the criteria at each position from the most significant
+// bit down, until only one is left. The stats are recounted over the remaining values each time.
+fn filter_by_bit_criteria<F: Fn(&BitColumnStats, usize) -> bool>(
+    report: &Report,
+    criteria: F,
+) -> u64 {
+    let mut values = report.values.clone();
+    for position in (0..report.width).rev() {
+        if values.len() == 1 {
+            break;
+        }
+        let stats = BitColumnStats::new(&values, report.width);
+        // If every value has the same bit here, the least common bit is one nobody has, and
+        // filtering on it would leave nothing
+        if stats.ones(position) == 0 || stats.zeros(position) == 0 {
+            continue;
+        }
+        let wanted_bit = criteria(&stats, position);
+        values.retain(|v| bit_at(*v, position) == wanted_bit);
+    }
+    values[0]
 }
 
 #[test]
@@ -101,5 +180,58 @@ fn test_part1() {
 #[test]
 fn test_part2() {
     let sample_data = read_file_to_vec(String::from("test_1.txt"));
-    assert_eq!(230, part2_recursive(&sample_data));
+    assert_eq!(230, part2(&sample_data));
+}
+
+#[test]
+fn test_wide_report() {
+    // 40 bits wide, so the rates don't fit in a u32
+    let report: Vec<String> = [
+        "1111000011110000111100001111000011110000",
+        "1111000011110000111100001111000011110001",
+        "0000000000000000000000000000000000000001",
+    ]
+    .iter()
+    .map(|l| l.to_string())
+    .collect();
+    let gamma = 0b1111000011110000111100001111000011110001_u128;
+    let epsilon = 0b0000111100001111000011110000111100001110_u128;
+    assert_eq!(gamma * epsilon, part1(&report));
+    let oxygen = 0b1111000011110000111100001111000011110001_u128;
+    let co2 = 0b0000000000000000000000000000000000000001_u128;
+    assert_eq!(oxygen * co2, part2(&report));
+}
+
+#[test]
+fn test_bit_column_stats() {
+    let sample_data = read_file_to_vec(String::from("test_1.txt"));
+    let report = parse_report(&sample_data);
+    let stats = BitColumnStats::new(&report.values, report.width);
+    assert_eq!(5, stats.width());
+    // Most significant column of the sample: 7 ones, 5 zeros
+    assert_eq!((7, 5), (stats.ones(4), stats.zeros(4)));
+    assert!(stats.most_common(4, TieBreak::Zero));
+    assert!(!stats.least_common(4, TieBreak::One));
+}
+
+#[test]
+fn test_ties() {
+    let report = parse_report(&["10".to_string(), "01".to_string()]);
+    let stats = BitColumnStats::new(&report.values, report.width);
+    for position in 0..2 {
+        assert_eq!((1, 1), (stats.ones(position), stats.zeros(position)));
+        assert!(stats.most_common(position, TieBreak::One));
+        assert!(!stats.most_common(position, TieBreak::Zero));
+        assert!(stats.least_common(position, TieBreak::One));
+        assert!(!stats.least_common(position, TieBreak::Zero));
+    }
+    // Oxygen keeps 1s on a tie, CO2 keeps 0s
+    assert_eq!(0b10, oxygen_generator_rating(&report));
+    assert_eq!(0b01, co2_scrubber_rating(&report));
+    // Ties in part 1 put the bit in gamma, not epsilon
+    assert_eq!(0, part1(&["10".to_string(), "01".to_string()]));
+    // A column where every remaining value agrees doesn't filter anything out
+    let agreeing = parse_report(&["011".to_string(), "010".to_string(), "001".to_string()]);
+    assert_eq!(0b011, oxygen_generator_rating(&agreeing));
+    assert_eq!(0b001, co2_scrubber_rating(&agreeing));
 }
@@ -1,19 +1,290 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::io::BufRead;
+use std::str::FromStr;
 
+// A way of winning. Each pattern is one or more lines of positions, and a board wins when every
+// position in any one line is marked.
+#[derive(Clone, Debug, PartialEq)]
+enum WinPattern {
+    Rows,
+    Columns,
+    Diagonals,
+    FourCorners,
+    FullCard,
+    // Positions to mark in reading order
+    Mask(Vec<bool>),
+}
+
+impl WinPattern {
+    // Named patterns fit any size of board, and a mask only fits the one it has a cell for
+    // every position of
+    fn fits(&self, size: usize) -> bool {
+        match self {
+            WinPattern::Mask(mask) => mask.len() == size * size,
+            _ => true,
+        }
+    }
+
+    fn lines(&self, size: usize) -> Vec<Vec<usize>> {
+        let last = size - 1;
+        match self {
+            WinPattern::Rows => (0..size)
+                .map(|row| (0..size).map(|column| row * size + column).collect())
+                .collect(),
+            WinPattern::Columns => (0..size)
+                .map(|column| (0..size).map(|row| row * size + column).collect())
+                .collect(),
+            WinPattern::Diagonals => vec![
+                (0..size).map(|i| i * size + i).collect(),
+                (0..size).map(|i| i * size + last - i).collect(),
+            ],
+            WinPattern::FourCorners => {
+                let mut corners = vec![0, last, last * size, last * size + last];
+                corners.dedup();
+                vec![corners]
+            }
+            WinPattern::FullCard => vec![(0..size * size).collect()],
+            WinPattern::Mask(mask) => {
+                assert_eq!(
+                    size * size,
+                    mask.len(),
+                    "mask doesn't fit a {0}x{0} board",
+                    size
+                );
+                vec![(0..mask.len()).filter(|i| mask[*i]).collect()]
+            }
+        }
+    }
+}
+
+impl FromStr for WinPattern {
+    type Err = String;
+
+    // A named pattern, or a mask like mask:10001/01010/00100/01010/10001 with one row per group
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rows" => Ok(WinPattern::Rows),
+            "columns" => Ok(WinPattern::Columns),
+            "diagonals" => Ok(WinPattern::Diagonals),
+            "corners" => Ok(WinPattern::FourCorners),
+            "full" => Ok(WinPattern::FullCard),
+            _ => match s.strip_prefix("mask:") {
+                Some(mask) => {
+                    let rows: Vec<&str> = mask.split('/').collect();
+                    if rows.iter().any(|row| row.len() != rows[0].len()) {
+                        return Err(format!("mask rows are not all the same length: {}", mask));
+                    }
+                    rows.concat()
+                        .chars()
+                        .map(|c| match c {
+                            '1' => Ok(true),
+                            '0' => Ok(false),
+                            _ => Err(format!("mask can only contain 0, 1 and /, got {}", c)),
+                        })
+                        .collect::<Result<Vec<bool>, String>>()
+                        .map(WinPattern::Mask)
+                }
+                None => Err(format!(
+                    "unknown pattern {}, expected rows, columns, diagonals, corners, full or mask:...",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for WinPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WinPattern::Rows => write!(f, "a row"),
+            WinPattern::Columns => write!(f, "a column"),
+            WinPattern::Diagonals => write!(f, "a diagonal"),
+            WinPattern::FourCorners => write!(f, "the four corners"),
+            WinPattern::FullCard => write!(f, "a full card"),
+            WinPattern::Mask(_) => write!(f, "the custom mask"),
+        }
+    }
+}
+
+// A line of positions that wins when all of them are marked
+#[derive(Debug)]
+struct WinLine {
+    pattern: usize,
+    length: usize,
+    marks: usize,
+}
+
+// An NxN bingo card. Marks are kept as a bitmap alongside a count of marks in each winning line,
+// so checking for a win after a call only looks at the lines through the position just marked.
 #[derive(Debug)]
-struct Board(Vec<Vec<u32>>);
+struct Board {
+    numbers: Vec<u32>,
+    positions: HashMap<u32, usize>,
+    marked: Vec<bool>,
+    lines: Vec<WinLine>,
+    lines_through: Vec<Vec<usize>>,
+    has_won: bool,
+}
+
+impl Board {
+    fn new(rows: Vec<Vec<u32>>, patterns: &[WinPattern]) -> Board {
+        let size = rows.len();
+        assert!(size > 0, "board is empty");
+        assert!(
+            rows.iter().all(|row| row.len() == size),
+            "board is not square: {:?}",
+            rows
+        );
+        let numbers: Vec<u32> = rows.into_iter().flatten().collect();
+        let mut lines = Vec::new();
+        let mut lines_through = vec![Vec::new(); numbers.len()];
+        for (pattern_index, pattern) in patterns.iter().enumerate() {
+            for line in pattern.lines(size) {
+                for position in &line {
+                    lines_through[*position].push(lines.len());
+                }
+                lines.push(WinLine {
+                    pattern: pattern_index,
+                    length: line.len(),
+                    marks: 0,
+                });
+            }
+        }
+        Board {
+            positions: numbers.iter().enumerate().map(|(i, n)| (*n, i)).collect(),
+            marked: vec![false; numbers.len()],
+            numbers,
+            lines,
+            lines_through,
+            has_won: false,
+        }
+    }
+
+    // Marks the number if it's on the board. If that completes a line, returns the index of the
+    // pattern it belongs to (the first one given, if it completes several).
+    fn mark(&mut self, number: u32) -> Option<usize> {
+        let position = match self.positions.get(&number) {
+            Some(position) if !self.marked[*position] => *position,
+            _ => return None,
+        };
+        self.marked[position] = true;
+        let mut won = None;
+        for line_index in &self.lines_through[position] {
+            let line = &mut self.lines[*line_index];
+            line.marks += 1;
+            if line.marks == line.length {
+                won = Some(won.map_or(line.pattern, |p: usize| p.min(line.pattern)));
+            }
+        }
+        won
+    }
+
+    fn unmarked_sum(&self) -> u32 {
+        self.numbers
+            .iter()
+            .zip(&self.marked)
+            .filter(|(_, marked)| !**marked)
+            .map(|(number, _)| number)
+            .sum()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct WinEvent {
+    board: usize,
+    call_index: usize,
+    pattern: WinPattern,
+    score: u32,
+}
+
+struct Bingo {
+    patterns: Vec<WinPattern>,
+    boards: Vec<Board>,
+}
+
+impl Bingo {
+    fn new(boards: Vec<Vec<Vec<u32>>>, patterns: &[WinPattern]) -> Bingo {
+        Bingo {
+            patterns: patterns.to_vec(),
+            boards: boards
+                .into_iter()
+                .map(|rows| Board::new(rows, patterns))
+                .collect(),
+        }
+    }
+
+    // Calls every number in turn, returning each board's win in the order they happen. Boards
+    // that have already won are left alone.
+    fn play(&mut self, calls: &[u32]) -> Vec<WinEvent> {
+        let mut wins = Vec::new();
+        for (call_index, number) in calls.iter().enumerate() {
+            for (board_index, board) in self.boards.iter_mut().enumerate() {
+                if board.has_won {
+                    continue;
+                }
+                if let Some(pattern) = board.mark(*number) {
+                    board.has_won = true;
+                    wins.push(WinEvent {
+                        board: board_index,
+                        call_index,
+                        pattern: self.patterns[pattern].clone(),
+                        score: board.unmarked_sum() * number,
+                    });
+                }
+            }
+        }
+        wins
+    }
+}
+
+const DEFAULT_PATTERNS: [WinPattern; 2] = [WinPattern::Rows, WinPattern::Columns];
 
+// Usage: day4 [file] [--pattern PATTERN]... [--report]
+// Patterns default to rows and columns.
 fn main() {
-    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let mut filename = String::from("input.txt");
+    let mut patterns = Vec::new();
+    let mut report = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pattern" => patterns.push(args.next().expect("--pattern PATTERN").parse().unwrap()),
+            "--report" => report = true,
+            _ => filename = arg,
+        }
+    }
+    if patterns.is_empty() {
+        patterns = DEFAULT_PATTERNS.to_vec();
+    }
     let parsed_lines = read_file_to_vec(filename);
-    let part_1_answer = part1(&parsed_lines);
-    let part_2_answer = part2(&parsed_lines);
+    let input = read_input(&parsed_lines);
+    for warning in &input.warnings {
+        println!("Warning: {}", warning);
+    }
+    check_patterns(&input, &patterns).unwrap_or_else(|e| panic!("{}", e));
+    let calls = input.calls.clone();
+    // One game gives both the first and the last winner
+    let wins = play(input, &patterns);
+    let part_1_answer = part1(&wins);
+    let part_2_answer = part2(&wins);
     println!("Part 1: {}", part_1_answer);
     println!("Part 2: {}", part_2_answer);
+    if report {
+        for win in &wins {
+            println!(
+                "Board {} won on {} when {} was called (call {}), scoring {}",
+                win.board + 1,
+                win.pattern,
+                calls[win.call_index],
+                win.call_index + 1,
+                win.score
+            );
+        }
+    }
 }
 
 fn read_file_to_vec(filename: String) -> Vec<String> {
@@ -21,118 +292,344 @@ fn read_file_to_vec(filename: String) -> Vec<String> {
     let file = fs::File::open(filename).unwrap();
     let reader = io::BufReader::new(file);
     let mut parsed_lines: Vec<String> = Vec::new();
-    for (_, line) in reader.lines().enumerate() {
+    for line in reader.lines() {
         parsed_lines.push(line.unwrap());
     }
     parsed_lines
 }
 
-fn read_boards(lines: &Vec<String>) -> Vec<Board> {
-    let mut current_board: Vec<Vec<u32>> = Vec::with_capacity(5);
-    let mut boards = Vec::new();
-    for i in 2..lines.len() {
-        if lines[i].is_empty() {
-            boards.push(Board(current_board.clone()));
-            current_board = Vec::with_capacity(5);
-            continue;
-        }
-        let mut current_row = Vec::with_capacity(5);
-        for (_, value) in lines[i].split_ascii_whitespace().enumerate() {
-            current_row.push(value.parse().unwrap());
+// The numbers to call and the boards, as read from the puzzle input
+#[derive(Debug)]
+struct BingoInput {
+    calls: Vec<u32>,
+    boards: Vec<Vec<Vec<u32>>>,
+    warnings: Vec<ParseWarning>,
+}
+
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    MissingCalls,
+    BadNumber { line: usize, value: String },
+    NotSquare { board: usize },
+    DuplicateOnBoard { board: usize, number: u32 },
+    PatternDoesNotFit { board: usize, size: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingCalls => write!(f, "input has no line of called numbers"),
+            ParseError::BadNumber { line, value } => {
+                write!(f, "line {}: {:?} is not a number", line, value)
+            }
+            ParseError::NotSquare { board } => write!(f, "board {} is not square", board),
+            ParseError::DuplicateOnBoard { board, number } => {
+                write!(f, "board {} has {} more than once", board, number)
+            }
+            ParseError::PatternDoesNotFit { board, size } => {
+                write!(
+                    f,
+                    "the custom mask doesn't fit board {0}, which is {1}x{1}",
+                    board, size
+                )
+            }
         }
-        current_board.push(current_row);
     }
-    boards.push(Board(current_board.clone()));
-    boards
 }
 
-fn part1(lines: &Vec<String>) -> u32 {
-    let all_called_numbers: Vec<u32> = lines
-        .iter()
-        .next()
-        .unwrap()
-        .split(",")
-        .enumerate()
-        .map(|(_, l)| l.parse().unwrap())
-        .collect();
-    let boards = read_boards(&lines);
-    for call_index in 4..all_called_numbers.len() {
-        let currently_called = &all_called_numbers[0..=call_index];
-        for board in &boards {
-            for i in 0..=4 {
-                if has_row_win(currently_called, &board, i)
-                    || has_column_win(currently_called, &board, i)
-                {
-                    return calculate_unmarked_sum(&board, currently_called)
-                        * all_called_numbers[call_index];
-                }
+impl std::error::Error for ParseError {}
+
+// Things that don't stop a game being played, but probably aren't what the input meant
+#[derive(Debug, PartialEq)]
+enum ParseWarning {
+    DuplicateCall(u32),
+    NeverCalled { board: usize, number: u32 },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::DuplicateCall(number) => {
+                write!(f, "{} is called more than once", number)
+            }
+            ParseWarning::NeverCalled { board, number } => {
+                write!(f, "{} on board {} is never called", number, board)
             }
         }
     }
-    0
 }
 
-fn part2(lines: &Vec<String>) -> u32 {
-    let all_called_numbers: Vec<u32> = lines
+// The first non-blank line is the called numbers, and every following run of non-blank lines is
+// a board, however many blank lines sit between them. Boards and lines are numbered from 1 in
+// errors and warnings.
+fn parse_input(lines: &[String]) -> Result<BingoInput, ParseError> {
+    let parse_number = |line: usize, value: &str| {
+        value
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| ParseError::BadNumber {
+                line: line + 1,
+                value: value.to_string(),
+            })
+    };
+    let mut numbered = lines
         .iter()
-        .next()
-        .unwrap()
-        .split(",")
         .enumerate()
-        .map(|(_, l)| l.parse().unwrap())
-        .collect();
-    let boards = read_boards(&lines);
-    let mut boards_with_wins = HashSet::new();
-    for call_index in 4..all_called_numbers.len() {
-        let currently_called = &all_called_numbers[0..=call_index];
-        for (board_num, board) in boards.iter().enumerate() {
-            for i in 0..=4 {
-                if has_row_win(currently_called, &board, i)
-                    || has_column_win(currently_called, &board, i)
-                {
-                    boards_with_wins.insert(board_num);
-                    if boards_with_wins.len() == boards.len() {
-                        return calculate_unmarked_sum(&board, currently_called)
-                            * all_called_numbers[call_index];
-                    }
-                }
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (calls_line, calls) = numbered.next().ok_or(ParseError::MissingCalls)?;
+    let calls = calls
+        .split(',')
+        .map(|value| parse_number(calls_line, value))
+        .collect::<Result<Vec<u32>, ParseError>>()?;
+
+    let mut boards: Vec<Vec<Vec<u32>>> = Vec::new();
+    let mut previous_line = calls_line;
+    for (line_index, line) in numbered {
+        let row = line
+            .split_ascii_whitespace()
+            .map(|value| parse_number(line_index, value))
+            .collect::<Result<Vec<u32>, ParseError>>()?;
+        if line_index > previous_line + 1 || boards.is_empty() {
+            boards.push(Vec::new());
+        }
+        boards.last_mut().unwrap().push(row);
+        previous_line = line_index;
+    }
+
+    let mut warnings = Vec::new();
+    let mut seen = HashSet::new();
+    for number in &calls {
+        if !seen.insert(*number) {
+            warnings.push(ParseWarning::DuplicateCall(*number));
+        }
+    }
+    for (board_index, rows) in boards.iter().enumerate() {
+        let board = board_index + 1;
+        if rows.iter().any(|row| row.len() != rows.len()) {
+            return Err(ParseError::NotSquare { board });
+        }
+        let mut on_board = HashSet::new();
+        for number in rows.iter().flatten() {
+            if !on_board.insert(*number) {
+                return Err(ParseError::DuplicateOnBoard {
+                    board,
+                    number: *number,
+                });
+            }
+            if !seen.contains(number) {
+                warnings.push(ParseWarning::NeverCalled {
+                    board,
+                    number: *number,
+                });
             }
         }
     }
-    0
+    Ok(BingoInput {
+        calls,
+        boards,
+        warnings,
+    })
 }
 
-fn has_row_win(currently_called: &[u32], board: &Board, index_to_check: usize) -> bool {
-    (0_usize..=4_usize).all(|j| currently_called.contains(&board.0[index_to_check][j]))
+// Every pattern has to fit every board before a game can start
+fn check_patterns(input: &BingoInput, patterns: &[WinPattern]) -> Result<(), ParseError> {
+    for (board_index, rows) in input.boards.iter().enumerate() {
+        if !patterns.iter().all(|pattern| pattern.fits(rows.len())) {
+            return Err(ParseError::PatternDoesNotFit {
+                board: board_index + 1,
+                size: rows.len(),
+            });
+        }
+    }
+    Ok(())
 }
 
-fn has_column_win(currently_called: &[u32], board: &Board, index_to_check: usize) -> bool {
-    (0_usize..=4_usize).all(|j| currently_called.contains(&board.0[j][index_to_check]))
+fn play(input: BingoInput, patterns: &[WinPattern]) -> Vec<WinEvent> {
+    Bingo::new(input.boards, patterns).play(&input.calls)
 }
 
-fn calculate_unmarked_sum(board: &Board, called_numbers: &[u32]) -> u32 {
-    let mut sum = 0;
-    for i in 0..=4 {
-        for j in 0..=4 {
-            if !called_numbers.contains(&board.0[i][j]) {
-                sum += board.0[i][j];
-            }
-        }
-    }
-    sum
+fn read_input(lines: &[String]) -> BingoInput {
+    parse_input(lines).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn part1(wins: &[WinEvent]) -> u32 {
+    wins.first().map_or(0, |win| win.score)
+}
+
+fn part2(wins: &[WinEvent]) -> u32 {
+    wins.last().map_or(0, |win| win.score)
 }
 
 #[test]
 fn test_part1() {
     let sample_data = read_file_to_vec(String::from("test.txt"));
-    let boards = read_boards(&sample_data);
-    println!("boards: {:?}", boards);
-    assert_eq!(3, boards.len());
-    assert_eq!(4512, part1(&sample_data));
+    let input = parse_input(&sample_data).unwrap();
+    assert_eq!(3, input.boards.len());
+    assert!(input.warnings.is_empty());
+    assert_eq!(
+        4512,
+        part1(&play(read_input(&sample_data), &DEFAULT_PATTERNS))
+    );
 }
 
 #[test]
 fn test_part2() {
     let sample_data = read_file_to_vec(String::from("test.txt"));
-    assert_eq!(1924, part2(&sample_data));
+    assert_eq!(
+        1924,
+        part2(&play(read_input(&sample_data), &DEFAULT_PATTERNS))
+    );
+}
+
+#[test]
+fn test_win_events() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let wins = play(read_input(&sample_data), &DEFAULT_PATTERNS);
+    assert_eq!(
+        vec![(2, 11), (0, 13), (1, 14)],
+        wins.iter()
+            .map(|win| (win.board, win.call_index))
+            .collect::<Vec<(usize, usize)>>()
+    );
+    // 3x3 boards, won on a column
+    let mut bingo = Bingo::new(
+        vec![
+            vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]],
+            vec![vec![1, 2, 3], vec![9, 5, 7], vec![4, 6, 8]],
+        ],
+        &DEFAULT_PATTERNS,
+    );
+    assert_eq!(
+        vec![
+            WinEvent {
+                board: 0,
+                call_index: 2,
+                pattern: WinPattern::Columns,
+                score: (2 + 3 + 5 + 6 + 8 + 9) * 7
+            },
+            WinEvent {
+                board: 1,
+                call_index: 3,
+                pattern: WinPattern::Columns,
+                score: (2 + 3 + 5 + 6 + 8) * 9
+            },
+        ],
+        bingo.play(&[1, 4, 7, 9, 3, 5])
+    );
+}
+
+#[test]
+fn test_win_patterns() {
+    let board = vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
+    let first_win = |patterns: &[WinPattern], calls: &[u32]| {
+        Bingo::new(vec![board.clone()], patterns)
+            .play(calls)
+            .first()
+            .map(|win| (win.call_index, win.pattern.clone()))
+    };
+    assert_eq!(
+        Some((2, WinPattern::Diagonals)),
+        first_win(&[WinPattern::Diagonals], &[3, 5, 7])
+    );
+    assert_eq!(None, first_win(&DEFAULT_PATTERNS, &[3, 5, 7]));
+    assert_eq!(
+        Some((3, WinPattern::FourCorners)),
+        first_win(&[WinPattern::FourCorners], &[1, 3, 7, 9])
+    );
+    assert_eq!(
+        Some((8, WinPattern::FullCard)),
+        first_win(&[WinPattern::FullCard], &[9, 8, 7, 6, 5, 4, 3, 2, 1])
+    );
+    let plus = WinPattern::Mask(vec![
+        false, true, false, true, true, true, false, true, false,
+    ]);
+    assert_eq!(Ok(plus.clone()), "mask:010/111/010".parse());
+    assert_eq!(
+        Some((4, plus.clone())),
+        first_win(&[plus], &[2, 4, 6, 8, 5, 1])
+    );
+    // A call completing two patterns at once reports the first one listed
+    assert_eq!(
+        Some((4, WinPattern::Diagonals)),
+        first_win(&[WinPattern::Diagonals, WinPattern::Rows], &[2, 3, 5, 9, 1])
+    );
+    assert_eq!(
+        Some((4, WinPattern::Rows)),
+        first_win(&[WinPattern::Rows, WinPattern::Diagonals], &[2, 3, 5, 9, 1])
+    );
+    assert!("mask:012".parse::<WinPattern>().is_err());
+    assert!("mask:010/11/010".parse::<WinPattern>().is_err());
+    assert!("zigzag".parse::<WinPattern>().is_err());
+}
+
+#[test]
+fn test_parse_input() {
+    let lines = |text: &str| text.lines().map(String::from).collect::<Vec<String>>();
+    let input = parse_input(&lines("\n1,2,3,2\n\n\n1 2\n3 4\n \n5 6\n7 8\n\n\n")).unwrap();
+    assert_eq!(vec![1, 2, 3, 2], input.calls);
+    assert_eq!(
+        vec![vec![vec![1, 2], vec![3, 4]], vec![vec![5, 6], vec![7, 8]]],
+        input.boards
+    );
+    assert_eq!(
+        vec![
+            ParseWarning::DuplicateCall(2),
+            ParseWarning::NeverCalled {
+                board: 1,
+                number: 4
+            },
+            ParseWarning::NeverCalled {
+                board: 2,
+                number: 5
+            },
+            ParseWarning::NeverCalled {
+                board: 2,
+                number: 6
+            },
+            ParseWarning::NeverCalled {
+                board: 2,
+                number: 7
+            },
+            ParseWarning::NeverCalled {
+                board: 2,
+                number: 8
+            },
+        ],
+        input.warnings
+    );
+
+    assert_eq!(
+        ParseError::MissingCalls,
+        parse_input(&lines("\n\n")).unwrap_err()
+    );
+    assert_eq!(
+        ParseError::NotSquare { board: 2 },
+        parse_input(&lines("1,2\n\n1 2\n3 4\n\n1 2\n3\n")).unwrap_err()
+    );
+    assert_eq!(
+        ParseError::NotSquare { board: 1 },
+        parse_input(&lines("1,2\n\n1 2\n3 4\n5 6\n")).unwrap_err()
+    );
+    assert_eq!(
+        ParseError::DuplicateOnBoard {
+            board: 1,
+            number: 3
+        },
+        parse_input(&lines("1,2\n\n1 3\n3 4\n")).unwrap_err()
+    );
+    assert_eq!(
+        ParseError::BadNumber {
+            line: 3,
+            value: String::from("x"),
+        },
+        parse_input(&lines("1,2\n\n1 x\n3 4\n")).unwrap_err()
+    );
+
+    let input = parse_input(&lines("1,2\n\n1 2\n3 4\n\n5 6 7\n8 9 1\n2 3 4\n")).unwrap();
+    let plus: WinPattern = "mask:010/111/010".parse().unwrap();
+    assert_eq!(
+        Err(ParseError::PatternDoesNotFit { board: 1, size: 2 }),
+        check_patterns(&input, &[WinPattern::Rows, plus])
+    );
+    assert_eq!(Ok(()), check_patterns(&input, &DEFAULT_PATTERNS));
 }
@@ -3,14 +3,387 @@ use std::env;
 use std::fs;
 use std::io;
 use std::io::BufRead;
+use std::io::Write;
+use std::str::FromStr;
 
+type Point = (i64, i64);
+
+// Bounding boxes up to this many cells are counted on a grid, anything bigger with a sweep
+const DENSE_GRID_MAX_CELLS: i64 = 1 << 24;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Segment {
+    start: Point,
+    end: Point,
+}
+
+impl FromStr for Segment {
+    type Err = String;
+
+    // x1,y1 -> x2,y2
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_point = |text: &str| -> Result<Point, String> {
+            let (x, y) = text
+                .trim()
+                .split_once(',')
+                .ok_or(format!("expected x,y, got {:?}", text))?;
+            let parse = |n: &str| {
+                n.trim()
+                    .parse()
+                    .map_err(|_| format!("bad coordinate {:?}", n))
+            };
+            Ok((parse(x)?, parse(y)?))
+        };
+        let (start, end) = s
+            .split_once("->")
+            .ok_or(format!("expected x1,y1 -> x2,y2, got {:?}", s))?;
+        Ok(Segment {
+            start: parse_point(start)?,
+            end: parse_point(end)?,
+        })
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Segment {
+    fn is_axis_aligned(&self) -> bool {
+        self.start.0 == self.end.0 || self.start.1 == self.end.1
+    }
+
+    // The smallest whole-number step along the segment, and how many of them it takes to reach
+    // the end. A single point has no direction and takes no steps.
+    fn step(&self) -> (Point, i64) {
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let steps = gcd(dx, dy);
+        if steps == 0 {
+            ((0, 0), 0)
+        } else {
+            ((dx / steps, dy / steps), steps)
+        }
+    }
+
+    // Every point with whole-number coordinates on the segment, from start to end
+    fn points(&self) -> impl Iterator<Item = Point> {
+        let ((step_x, step_y), steps) = self.step();
+        let start = self.start;
+        (0..=steps).map(move |k| (start.0 + k * step_x, start.1 + k * step_y))
+    }
+
+    fn min_x(&self) -> i64 {
+        self.start.0.min(self.end.0)
+    }
+
+    fn max_x(&self) -> i64 {
+        self.start.0.max(self.end.0)
+    }
+
+    // How many steps from the start a point is, if it's one of the segment's lattice points
+    fn steps_to(&self, point: Point) -> Option<i64> {
+        let ((step_x, step_y), steps) = self.step();
+        let (dx, dy) = (point.0 - self.start.0, point.1 - self.start.1);
+        let k = if step_x != 0 {
+            dx / step_x
+        } else if step_y != 0 {
+            dy / step_y
+        } else {
+            0
+        };
+        (k >= 0 && k <= steps && dx == k * step_x && dy == k * step_y).then_some(k)
+    }
+
+    // The lattice points the two segments share. Crossing segments share at most one, found
+    // exactly with integer arithmetic, and collinear ones share a run of them.
+    fn intersections(&self, other: &Segment) -> Vec<Point> {
+        // Products of coordinates outgrow i64 once they reach the billions, so work in i128
+        let cross = |a: Point, b: Point| a.0 as i128 * b.1 as i128 - a.1 as i128 * b.0 as i128;
+        let r = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let s = (other.end.0 - other.start.0, other.end.1 - other.start.1);
+        let offset = (other.start.0 - self.start.0, other.start.1 - self.start.1);
+        let denominator = cross(r, s);
+        if denominator != 0 {
+            // self.start + r * t == other.start + s * u, with t = t_numerator / denominator
+            let t_numerator = cross(offset, s);
+            let (x, y) = (
+                self.start.0 as i128 * denominator + t_numerator * r.0 as i128,
+                self.start.1 as i128 * denominator + t_numerator * r.1 as i128,
+            );
+            if x % denominator != 0 || y % denominator != 0 {
+                return Vec::new();
+            }
+            let point = ((x / denominator) as i64, (y / denominator) as i64);
+            return match (self.steps_to(point), other.steps_to(point)) {
+                (Some(_), Some(_)) => vec![point],
+                _ => Vec::new(),
+            };
+        }
+        if cross(offset, r) != 0 || cross(offset, s) != 0 {
+            // Parallel but on different lines
+            return Vec::new();
+        }
+        if self.start == self.end {
+            return other
+                .steps_to(self.start)
+                .map_or(Vec::new(), |_| vec![self.start]);
+        }
+        // On the same line, so both segments are runs of the same lattice. Find where the other
+        // segment's ends fall along this one and keep the part they have in common.
+        let ((step_x, step_y), steps) = self.step();
+        let along = |p: Point| {
+            let (dx, dy) = (p.0 - self.start.0, p.1 - self.start.1);
+            let dot = dx as i128 * step_x as i128 + dy as i128 * step_y as i128;
+            (dot / (step_x as i128 * step_x as i128 + step_y as i128 * step_y as i128)) as i64
+        };
+        let (a, b) = (along(other.start), along(other.end));
+        let (from, to) = (a.min(b).max(0), a.max(b).min(steps));
+        (from..=to)
+            .map(|k| (self.start.0 + k * step_x, self.start.1 + k * step_y))
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Strategy {
+    DenseGrid,
+    Sweep,
+}
+
+fn choose_strategy(segments: &[Segment]) -> Strategy {
+    let points = segments.iter().flat_map(|s| [s.start, s.end]);
+    let (min_x, max_x) = (
+        points.clone().map(|p| p.0).min(),
+        points.clone().map(|p| p.0).max(),
+    );
+    let (min_y, max_y) = (points.clone().map(|p| p.1).min(), points.map(|p| p.1).max());
+    match (min_x, max_x, min_y, max_y) {
+        (Some(min_x), Some(max_x), Some(min_y), Some(max_y))
+            if (max_x - min_x + 1).saturating_mul(max_y - min_y + 1) <= DENSE_GRID_MAX_CELLS =>
+        {
+            Strategy::DenseGrid
+        }
+        _ => Strategy::Sweep,
+    }
+}
+
+// Counts how many segments cover each cell of the bounding box, stopping at 2
+fn count_overlaps_dense(segments: &[Segment]) -> usize {
+    let points = || segments.iter().flat_map(|s| [s.start, s.end]);
+    let (min_x, min_y) = match (points().map(|p| p.0).min(), points().map(|p| p.1).min()) {
+        (Some(min_x), Some(min_y)) => (min_x, min_y),
+        _ => return 0,
+    };
+    let width = (points().map(|p| p.0).max().unwrap() - min_x + 1) as usize;
+    let height = (points().map(|p| p.1).max().unwrap() - min_y + 1) as usize;
+    let mut grid = vec![0u8; width * height];
+    let mut overlaps = 0;
+    for point in segments.iter().flat_map(|s| s.points()) {
+        let cell = &mut grid[(point.1 - min_y) as usize * width + (point.0 - min_x) as usize];
+        if *cell == 1 {
+            overlaps += 1;
+        }
+        *cell = (*cell + 1).min(2);
+    }
+    overlaps
+}
+
+// Sweeps left to right, intersecting each segment only with the ones whose x range it reaches
+fn count_overlaps_sweep(segments: &[Segment]) -> usize {
+    let mut by_min_x: Vec<&Segment> = segments.iter().collect();
+    by_min_x.sort_by_key(|s| s.min_x());
+    let mut active: Vec<&Segment> = Vec::new();
+    let mut overlapping = HashSet::new();
+    for segment in by_min_x {
+        active.retain(|a| a.max_x() >= segment.min_x());
+        for other in &active {
+            overlapping.extend(segment.intersections(other));
+        }
+        active.push(segment);
+    }
+    overlapping.len()
+}
+
+fn count_overlaps(segments: &[Segment]) -> usize {
+    match choose_strategy(segments) {
+        Strategy::DenseGrid => count_overlaps_dense(segments),
+        Strategy::Sweep => count_overlaps_sweep(segments),
+    }
+}
+
+// Diagrams wider or taller than this are only written as heatmap images
+const DIAGRAM_MAX_SIZE: usize = 100;
+
+// How many segments cover each point, over a grid from the origin (or the furthest negative
+// point) to the furthest point. Segments far from the origin get a grid of just their own
+// bounding box.
+struct Heatmap {
+    origin: Point,
+    width: usize,
+    height: usize,
+    counts: Vec<u32>,
+}
+
+impl Heatmap {
+    // None if the grid would be bigger than DENSE_GRID_MAX_CELLS
+    fn new(segments: &[Segment]) -> Option<Heatmap> {
+        let points = || segments.iter().flat_map(|s| [s.start, s.end]);
+        let min = (
+            points().map(|p| p.0).min().unwrap_or(0),
+            points().map(|p| p.1).min().unwrap_or(0),
+        );
+        let max = (
+            points().map(|p| p.0).max().unwrap_or(0),
+            points().map(|p| p.1).max().unwrap_or(0),
+        );
+        let cells = |origin: Point| (max.0 - origin.0 + 1).saturating_mul(max.1 - origin.1 + 1);
+        let with_origin = (min.0.min(0), min.1.min(0));
+        let origin = if cells(with_origin) <= DENSE_GRID_MAX_CELLS {
+            with_origin
+        } else if cells(min) <= DENSE_GRID_MAX_CELLS {
+            min
+        } else {
+            return None;
+        };
+        let width = (max.0 - origin.0 + 1) as usize;
+        let height = (max.1 - origin.1 + 1) as usize;
+        let mut counts = vec![0; width * height];
+        for point in segments.iter().flat_map(|s| s.points()) {
+            counts[(point.1 - origin.1) as usize * width + (point.0 - origin.0) as usize] += 1;
+        }
+        Some(Heatmap {
+            origin,
+            width,
+            height,
+            counts,
+        })
+    }
+
+    // The highest count, and every point that has it
+    fn max_overlap(&self) -> (u32, Vec<Point>) {
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        let points = (0..self.counts.len())
+            .filter(|i| self.counts[*i] == max)
+            .map(|i| {
+                (
+                    self.origin.0 + (i % self.width) as i64,
+                    self.origin.1 + (i / self.width) as i64,
+                )
+            })
+            .collect();
+        (max, points)
+    }
+
+    // The puzzle's diagram: . for no vents, otherwise the count (# past 9)
+    fn to_diagram(&self) -> String {
+        let mut diagram = String::new();
+        for row in self.counts.chunks(self.width) {
+            for count in row {
+                diagram.push(match count {
+                    0 => '.',
+                    1..=9 => char::from_digit(*count, 10).unwrap(),
+                    _ => '#',
+                });
+            }
+            diagram.push('\n');
+        }
+        diagram
+    }
+
+    // Greyscale, brighter for more overlaps
+    fn to_greyscale(&self) -> Vec<u8> {
+        let max = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        self.counts
+            .iter()
+            .map(|count| (count * 255 / max) as u8)
+            .collect()
+    }
+
+    fn to_pgm(&self) -> Vec<u8> {
+        let mut pgm = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        pgm.extend(self.to_greyscale());
+        pgm
+    }
+
+    fn write_png<W: Write>(&self, out: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_greyscale())?;
+        writer.finish()
+    }
+}
+
+// Usage: day5 [file] [--diagram] [--heatmap FILE]
+// The heatmap is a PNG if FILE ends in .png and a PGM otherwise. Both cover every segment,
+// diagonals included.
 fn main() {
-    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let mut filename = String::from("input.txt");
+    let mut diagram = false;
+    let mut heatmap_file: Option<String> = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--diagram" => diagram = true,
+            "--heatmap" => heatmap_file = Some(args.next().expect("--heatmap FILE")),
+            _ => filename = arg,
+        }
+    }
     let parsed_lines = read_file_to_vec(filename);
     let part_1_answer = part1(&parsed_lines);
     let part_2_answer = part2(&parsed_lines);
     println!("Part 1: {}", part_1_answer);
     println!("Part 2: {}", part_2_answer);
+
+    let segments = read_segments(&parsed_lines);
+    let heatmap = match Heatmap::new(&segments) {
+        Some(heatmap) => heatmap,
+        None => {
+            println!("Too large to map overlaps");
+            return;
+        }
+    };
+    let (max, points) = heatmap.max_overlap();
+    let shown: Vec<String> = points
+        .iter()
+        .take(10)
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect();
+    println!(
+        "Most overlaps: {} at {} point(s): {}{}",
+        max,
+        points.len(),
+        shown.join(" "),
+        if points.len() > shown.len() {
+            " ..."
+        } else {
+            ""
+        }
+    );
+    if diagram {
+        if heatmap.width <= DIAGRAM_MAX_SIZE && heatmap.height <= DIAGRAM_MAX_SIZE {
+            print!("{}", heatmap.to_diagram());
+        } else {
+            println!(
+                "{}x{} is too large for a diagram, use --heatmap instead",
+                heatmap.width, heatmap.height
+            );
+        }
+    }
+    if let Some(heatmap_file) = heatmap_file {
+        if heatmap_file.ends_with(".png") {
+            let file = fs::File::create(&heatmap_file).unwrap();
+            heatmap.write_png(io::BufWriter::new(file)).unwrap();
+        } else {
+            fs::write(&heatmap_file, heatmap.to_pgm()).unwrap();
+        }
+        println!("Wrote {}", heatmap_file);
+    }
 }
 
 fn read_file_to_vec(filename: String) -> Vec<String> {
@@ -18,61 +391,34 @@ fn read_file_to_vec(filename: String) -> Vec<String> {
     let file = fs::File::open(filename).unwrap();
     let reader = io::BufReader::new(file);
     let mut parsed_lines: Vec<String> = Vec::new();
-    for (_, line) in reader.lines().enumerate() {
+    for line in reader.lines() {
         parsed_lines.push(line.unwrap());
     }
     parsed_lines
 }
 
-fn part1(lines: &Vec<String>) -> u32 {
-    overlapping_points(lines, false)
+fn read_segments(lines: &[String]) -> Vec<Segment> {
+    lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.parse().unwrap_or_else(|e| panic!("{}", e)))
+        .collect()
 }
 
-fn overlapping_points(lines: &Vec<String>, include_diagonal: bool) -> u32 {
-    let mut overlapping_points = HashSet::new();
-    let mut seen_points = HashSet::new();
-    for line in lines {
-        let mut parts = line.split_ascii_whitespace();
-        let mut point1_parts = parts.next().unwrap().split(",");
-        let start_point: (u32, u32) = (
-            point1_parts.next().unwrap().parse().unwrap(),
-            point1_parts.next().unwrap().parse().unwrap(),
-        );
-        parts.next();
-        let mut point2_parts = parts.next().unwrap().split(",");
-        let end_point: (u32, u32) = (
-            point2_parts.next().unwrap().parse().unwrap(),
-            point2_parts.next().unwrap().parse().unwrap(),
-        );
-        if include_diagonal || start_point.0 == end_point.0 || start_point.1 == end_point.1 {
-            let points_in_line = all_points_in_line(&start_point, &end_point);
-            for point in points_in_line {
-                if seen_points.contains(&point) {
-                    overlapping_points.insert(point.clone());
-                }
-                seen_points.insert(point.clone());
-            }
-        }
-    }
-    overlapping_points.len() as u32
+fn part1(lines: &[String]) -> usize {
+    overlapping_points(lines, false)
 }
 
-fn all_points_in_line(start_point: &(u32, u32), end_point: &(u32, u32)) -> Vec<(u32, u32)> {
-    let mut points = Vec::new();
-    let slope_x = (end_point.0 as i32 - start_point.0 as i32).signum();
-    let slope_y = (end_point.1 as i32 - start_point.1 as i32).signum();
-    let mut x = start_point.0;
-    let mut y = start_point.1;
-    while &(x, y) != end_point {
-        points.push((x, y));
-        x = (x as i32 + slope_x) as u32;
-        y = (y as i32 + slope_y) as u32;
-    }
-    points.push((x, y));
-    points
+// Without diagonals only horizontal and vertical segments count; with them, any slope does
+fn overlapping_points(lines: &[String], include_diagonal: bool) -> usize {
+    let segments: Vec<Segment> = read_segments(lines)
+        .into_iter()
+        .filter(|segment| include_diagonal || segment.is_axis_aligned())
+        .collect();
+    count_overlaps(&segments)
 }
 
-fn part2(lines: &Vec<String>) -> u32 {
+fn part2(lines: &[String]) -> usize {
     overlapping_points(lines, true)
 }
 
@@ -85,13 +431,136 @@ fn test_part1() {
 #[test]
 fn test_part2() {
     let sample_data = read_file_to_vec(String::from("test.txt"));
+    let points = |line: &str| {
+        line.parse::<Segment>()
+            .unwrap()
+            .points()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec![(1, 1), (2, 2), (3, 3)], points("1,1 -> 3,3"));
+    assert_eq!(vec![(9, 7), (8, 8), (7, 9)], points("9,7 -> 7,9"));
+    assert_eq!(12, part2(&sample_data));
+}
+
+#[test]
+fn test_segments() {
+    let segment = |line: &str| line.parse::<Segment>().unwrap();
     assert_eq!(
-        vec![(1, 1), (2, 2), (3, 3)],
-        all_points_in_line(&(1, 1), &(3, 3))
+        vec![(0, 0), (2, 1), (4, 2), (6, 3)],
+        segment("0,0 -> 6,3").points().collect::<Vec<_>>()
     );
     assert_eq!(
-        vec![(9, 7), (8, 8), (7, 9)],
-        all_points_in_line(&(9, 7), &(7, 9))
+        vec![(5, 5)],
+        segment("5,5 -> 5,5").points().collect::<Vec<_>>()
     );
-    assert_eq!(12, part2(&sample_data));
+
+    // Crossing at a lattice point, crossing between lattice points, and missing entirely
+    assert_eq!(
+        vec![(2, 1)],
+        segment("0,0 -> 6,3").intersections(&segment("2,0 -> 2,5"))
+    );
+    assert!(segment("0,0 -> 6,3")
+        .intersections(&segment("1,0 -> 1,5"))
+        .is_empty());
+    assert!(segment("0,0 -> 2,2")
+        .intersections(&segment("3,0 -> 3,5"))
+        .is_empty());
+    // Collinear runs, in either direction, and parallel lines that never meet
+    assert_eq!(
+        vec![(2, 1), (4, 2)],
+        segment("0,0 -> 4,2").intersections(&segment("8,4 -> 2,1"))
+    );
+    assert!(segment("0,0 -> 4,2")
+        .intersections(&segment("0,1 -> 4,3"))
+        .is_empty());
+    assert_eq!(
+        vec![(3, 3)],
+        segment("3,3 -> 3,3").intersections(&segment("1,1 -> 5,5"))
+    );
+
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let sample = read_segments(&sample_data);
+    assert_eq!(Strategy::DenseGrid, choose_strategy(&sample));
+    assert_eq!(12, count_overlaps_sweep(&sample));
+    let steep = [
+        segment("0,0 -> 6,3"),
+        segment("2,0 -> 2,5"),
+        segment("6,0 -> 0,3"),
+        segment("0,3 -> 8,7"),
+        segment("4,2 -> 4,2"),
+    ];
+    assert_eq!(5, count_overlaps_dense(&steep));
+    assert_eq!(5, count_overlaps_sweep(&steep));
+    let far_apart = [
+        segment("0,0 -> 1000000,1000000"),
+        segment("0,1000000 -> 1000000,0"),
+    ];
+    assert_eq!(Strategy::Sweep, choose_strategy(&far_apart));
+    assert_eq!(1, count_overlaps(&far_apart));
+
+    // Coordinates whose products don't fit in an i64
+    let big = 3_000_000_000;
+    let crossing = [
+        segment("0,0 -> 6000000000,6000000000"),
+        segment("0,6000000000 -> 6000000000,0"),
+        segment("-3000000000,3000000000 -> 9000000000,3000000000"),
+    ];
+    assert_eq!(vec![(big, big)], crossing[0].intersections(&crossing[1]));
+    assert_eq!(
+        vec![(big, big), (big + 1, big + 1)],
+        segment("3000000000,3000000000 -> 3000000001,3000000001")
+            .intersections(&segment("0,0 -> 6000000000,6000000000"))
+    );
+    assert_eq!(Strategy::Sweep, choose_strategy(&crossing));
+    assert_eq!(1, count_overlaps(&crossing));
+}
+
+#[test]
+fn test_heatmap() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let heatmap = Heatmap::new(&read_segments(&sample_data)).unwrap();
+    assert_eq!(
+        "1.1....11.\n\
+         .111...2..\n\
+         ..2.1.111.\n\
+         ...1.2.2..\n\
+         .112313211\n\
+         ...1.2....\n\
+         ..1...1...\n\
+         .1.....1..\n\
+         1.......1.\n\
+         222111....\n",
+        heatmap.to_diagram()
+    );
+    assert_eq!((3, vec![(4, 4), (6, 4)]), heatmap.max_overlap());
+    let pgm = heatmap.to_pgm();
+    assert!(pgm.starts_with(b"P5\n10 10\n255\n"));
+    assert_eq!(255, pgm[pgm.len() - 100 + 44]);
+    assert_eq!(85, pgm[pgm.len() - 100]);
+
+    let offset = Heatmap::new(&[
+        "-2,1 -> 0,1".parse().unwrap(),
+        "-1,0 -> -1,2".parse().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(".1.\n121\n.1.\n", offset.to_diagram());
+    assert_eq!((2, vec![(-1, 1)]), offset.max_overlap());
+
+    // Too far out to include the origin, so the grid covers just the segments
+    let far = Heatmap::new(&[
+        "999999999,999999999 -> 1000000001,1000000001"
+            .parse()
+            .unwrap(),
+        "999999999,1000000001 -> 1000000001,999999999"
+            .parse()
+            .unwrap(),
+    ])
+    .unwrap();
+    assert_eq!((999999999, 999999999), far.origin);
+    assert_eq!("1.1\n.2.\n1.1\n", far.to_diagram());
+    let huge = Heatmap::new(&[
+        "0,0 -> 0,0".parse().unwrap(),
+        "1000000,1000000 -> 1000000,1000000".parse().unwrap(),
+    ]);
+    assert!(huge.is_none());
 }
@@ -1,13 +1,136 @@
-use std::collections::HashMap;
+use num_bigint::BigUint;
 use std::env;
 use std::fs;
 use std::io;
 use std::io::BufRead;
-use std::ops::Sub;
 
+// When a fish's timer passes 0 it goes back to reset and a new fish starts at newborn
+#[derive(Clone, Copy, Debug)]
+struct Timers {
+    reset: usize,
+    newborn: usize,
+}
+
+const PUZZLE_TIMERS: Timers = Timers {
+    reset: 6,
+    newborn: 8,
+};
+
+impl Timers {
+    fn states(&self) -> usize {
+        self.reset.max(self.newborn) + 1
+    }
+}
+
+// Square matrix of fish counts, where entry [to][from] is how many fish with timer `to` one fish
+// with timer `from` becomes
+#[derive(Clone, Debug, PartialEq)]
+struct Matrix(Vec<Vec<BigUint>>);
+
+impl Matrix {
+    fn identity(size: usize) -> Matrix {
+        Matrix(
+            (0..size)
+                .map(|row| {
+                    (0..size)
+                        .map(|column| BigUint::from((row == column) as u32))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    // One day: every timer counts down, and fish at 0 both reset and spawn
+    fn one_day(timers: Timers) -> Matrix {
+        let size = timers.states();
+        let mut matrix = Matrix(vec![vec![BigUint::from(0u32); size]; size]);
+        for from in 1..size {
+            matrix.0[from - 1][from] += 1u32;
+        }
+        matrix.0[timers.reset][0] += 1u32;
+        matrix.0[timers.newborn][0] += 1u32;
+        matrix
+    }
+
+    fn multiply(&self, other: &Matrix) -> Matrix {
+        let size = self.0.len();
+        let mut product = Matrix(vec![vec![BigUint::from(0u32); size]; size]);
+        for row in 0..size {
+            for k in 0..size {
+                if self.0[row][k] == BigUint::from(0u32) {
+                    continue;
+                }
+                for column in 0..size {
+                    product.0[row][column] += &self.0[row][k] * &other.0[k][column];
+                }
+            }
+        }
+        product
+    }
+
+    // By repeated squaring, so a million days is only ~40 multiplications
+    fn pow(&self, mut exponent: usize) -> Matrix {
+        let mut result = Matrix::identity(self.0.len());
+        let mut square = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&square);
+            }
+            square = square.multiply(&square);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    fn apply(&self, counts: &[BigUint]) -> Vec<BigUint> {
+        self.0
+            .iter()
+            .map(|row| row.iter().zip(counts).map(|(m, c)| m * c).sum())
+            .collect()
+    }
+}
+
+// Usage: day6 [file] [--days N] [--reset N] [--newborn N] [--csv FILE]
+// With any of the options, prints the population after N days (default 256) with those timers
+// instead of the puzzle answers. --csv also writes the population on every day up to then.
 fn main() {
-    let filename = env::args().nth(1).unwrap_or(String::from("input.txt"));
+    let mut filename = String::from("input.txt");
+    let mut days = None;
+    let mut timers = None;
+    let mut csv_file = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--days" => days = Some(args.next().expect("--days N").parse().unwrap()),
+            "--reset" => {
+                timers = Some(Timers {
+                    reset: args.next().expect("--reset N").parse().unwrap(),
+                    ..timers.unwrap_or(PUZZLE_TIMERS)
+                })
+            }
+            "--newborn" => {
+                timers = Some(Timers {
+                    newborn: args.next().expect("--newborn N").parse().unwrap(),
+                    ..timers.unwrap_or(PUZZLE_TIMERS)
+                })
+            }
+            "--csv" => csv_file = Some(args.next().expect("--csv FILE")),
+            _ => filename = arg,
+        }
+    }
     let parsed_lines = read_file_to_vec(filename);
+    if days.is_some() || timers.is_some() || csv_file.is_some() {
+        let days = days.unwrap_or(256);
+        let timers = timers.unwrap_or(PUZZLE_TIMERS);
+        let fish = population(&parsed_lines, days, timers);
+        println!("After {} days: {}", days, fish);
+        if let Some(csv_file) = csv_file {
+            let series = population_series(&parsed_lines, days, timers);
+            fs::write(&csv_file, to_csv(&series)).unwrap();
+            println!("Wrote {}", csv_file);
+        }
+        return;
+    }
     let part_1_answer = part1(&parsed_lines);
     let part_2_answer = part2(&parsed_lines);
     println!("Part 1: {}", part_1_answer);
@@ -19,59 +142,161 @@ fn read_file_to_vec(filename: String) -> Vec<String> {
     let file = fs::File::open(filename).unwrap();
     let reader = io::BufReader::new(file);
     let mut parsed_lines: Vec<String> = Vec::new();
-    for (_, line) in reader.lines().enumerate() {
+    for line in reader.lines() {
         parsed_lines.push(line.unwrap());
     }
     parsed_lines
 }
 
-fn part1(lines: &Vec<String>) -> u64 {
+fn part1(lines: &[String]) -> BigUint {
     run_laternfish_simulation(lines, 80)
 }
 
-fn part2(lines: &Vec<String>) -> u64 {
+fn part2(lines: &[String]) -> BigUint {
     run_laternfish_simulation(lines, 256)
 }
 
-fn run_laternfish_simulation(lines: &Vec<String>, num_days: usize) -> u64 {
-    let initial_state: Vec<u64> = lines
-        .iter()
-        .next()
-        .unwrap()
-        .split(",")
-        .map(|f| f.parse().unwrap())
-        .collect();
-    let mut counts_by_time_left: HashMap<u64, u64> = HashMap::new();
-    let mut total_fish: u64 = initial_state.len() as u64;
-    for state in initial_state {
-        *counts_by_time_left.entry(state).or_insert(0) += 1;
+fn run_laternfish_simulation(lines: &[String], num_days: usize) -> BigUint {
+    population(lines, num_days, PUZZLE_TIMERS)
+}
+
+// How many fish have each timer, from the comma separated timers on the first line
+fn initial_counts(lines: &[String], timers: Timers) -> Vec<BigUint> {
+    let mut counts = vec![BigUint::from(0u32); timers.states()];
+    for timer in lines[0].split(',') {
+        let timer: usize = timer.trim().parse().unwrap();
+        assert!(
+            timer < counts.len(),
+            "timer {} is past the longest timer {}",
+            timer,
+            counts.len() - 1
+        );
+        counts[timer] += 1u32;
+    }
+    counts
+}
+
+// How many fish there are after num_days
+fn population(lines: &[String], num_days: usize, timers: Timers) -> BigUint {
+    Matrix::one_day(timers)
+        .pow(num_days)
+        .apply(&initial_counts(lines, timers))
+        .into_iter()
+        .sum()
+}
+
+// The count for each timer on every day from 0 to num_days, one day at a time
+fn population_series(lines: &[String], num_days: usize, timers: Timers) -> Vec<Vec<BigUint>> {
+    let one_day = Matrix::one_day(timers);
+    let mut series = vec![initial_counts(lines, timers)];
+    for _ in 0..num_days {
+        let next = one_day.apply(series.last().unwrap());
+        series.push(next);
+    }
+    series
+}
+
+fn to_csv(series: &[Vec<BigUint>]) -> String {
+    let states = series.first().map_or(0, |counts| counts.len());
+    let mut csv = String::from("day,total");
+    for timer in 0..states {
+        csv.push_str(&format!(",timer_{}", timer));
     }
-    let mut adds_by_day = vec![0; num_days + 1];
-    for day in 1..=num_days {
-        let spawning_timers: u64 = ((day - 1) % 7) as u64;
-        let num_to_ignore = if day > 2 { adds_by_day[day - 2] } else { 0 };
-        let count_at_time_left = counts_by_time_left
-            .get(&spawning_timers)
-            .unwrap_or(&0)
-            .clone();
-        let count_at_timer = count_at_time_left.sub(num_to_ignore);
-        let position_to_add_at = (spawning_timers + 2) % 7;
-        *counts_by_time_left.entry(position_to_add_at).or_insert(0) += count_at_timer;
-        total_fish += count_at_timer;
-        adds_by_day.insert(day, count_at_timer.clone());
+    csv.push('\n');
+    for (day, counts) in series.iter().enumerate() {
+        let total: BigUint = counts.iter().sum();
+        csv.push_str(&format!("{},{}", day, total));
+        for count in counts {
+            csv.push_str(&format!(",{}", count));
+        }
+        csv.push('\n');
     }
-    total_fish
+    csv
 }
 
 #[test]
 fn test_part1() {
     let sample_data = read_file_to_vec(String::from("test.txt"));
-    assert_eq!(26, run_laternfish_simulation(&sample_data, 18));
-    assert_eq!(5934, part1(&sample_data));
+    assert_eq!(
+        BigUint::from(26u32),
+        run_laternfish_simulation(&sample_data, 18)
+    );
+    assert_eq!(BigUint::from(5934u32), part1(&sample_data));
 }
 
 #[test]
 fn test_part2() {
     let sample_data = read_file_to_vec(String::from("test.txt"));
-    assert_eq!(26984457539, part2(&sample_data));
+    assert_eq!(BigUint::from(26984457539u64), part2(&sample_data));
+}
+
+#[test]
+fn test_population() {
+    // Fish one at a time, for checking the matrix against
+    let simulate = |initial: &[usize], days: usize, timers: Timers| {
+        let mut fish = initial.to_vec();
+        for _ in 0..days {
+            let spawned = fish.iter().filter(|timer| **timer == 0).count();
+            for timer in fish.iter_mut() {
+                *timer = if *timer == 0 {
+                    timers.reset
+                } else {
+                    *timer - 1
+                };
+            }
+            fish.extend(vec![timers.newborn; spawned]);
+        }
+        BigUint::from(fish.len())
+    };
+    let sample = vec![String::from("3,4,3,1,2")];
+    for timers in [
+        PUZZLE_TIMERS,
+        Timers {
+            reset: 2,
+            newborn: 4,
+        },
+        Timers {
+            reset: 5,
+            newborn: 3,
+        },
+    ] {
+        for days in [0, 1, 7, 30] {
+            assert_eq!(
+                simulate(&[3, 4, 3, 1, 2], days, timers),
+                population(&sample, days, timers)
+            );
+        }
+    }
+
+    // Far past where u64 overflows. With the puzzle's timers the total satisfies
+    // N(d) = N(d - 7) + N(d - 9), which makes a check that doesn't need the exact number.
+    let fish = population(&sample, 10_000, PUZZLE_TIMERS);
+    assert!(fish > BigUint::from(u64::MAX));
+    assert_eq!(
+        population(&sample, 9_993, PUZZLE_TIMERS) + population(&sample, 9_991, PUZZLE_TIMERS),
+        fish
+    );
+}
+
+#[test]
+fn test_population_series() {
+    let sample_data = read_file_to_vec(String::from("test.txt"));
+    let series = population_series(&sample_data, 18, PUZZLE_TIMERS);
+    assert_eq!(19, series.len());
+    for (day, counts) in series.iter().enumerate() {
+        assert_eq!(
+            population(&sample_data, day, PUZZLE_TIMERS),
+            counts.iter().sum()
+        );
+    }
+    // Day 3 of the puzzle's example is 0,1,0,5,6,7,8
+    let csv = to_csv(&series);
+    let mut lines = csv.lines();
+    assert_eq!(
+        Some("day,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8"),
+        lines.next()
+    );
+    assert_eq!(Some("0,5,0,1,1,2,1,0,0,0,0"), lines.next());
+    assert_eq!(Some("3,7,2,1,0,0,0,1,1,1,1"), lines.nth(2));
+    assert_eq!(Some("18,26,3,5,3,2,2,1,5,1,4"), csv.lines().last());
 }